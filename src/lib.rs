@@ -1,6 +1,9 @@
 // #![feature(const_int_pow)]
 
+pub mod location;
 pub mod topology;
 pub mod object;
+pub mod random;
+pub mod noise;
 
 pub use object::Object;
\ No newline at end of file
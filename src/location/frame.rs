@@ -0,0 +1,94 @@
+use glam::{Vec3, Mat3, Mat4, Quat};
+use super::Position;
+
+/// Local tangent frame (East-North-Up).
+///
+/// Gives a consistent "up" and heading reference anywhere around a spherical object.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frame {
+    pub east: Vec3,
+    pub north: Vec3,
+    pub up: Vec3
+}
+
+impl Frame {
+    /// Frame at the given direction, aligned with the sphere normal.
+    pub fn new(dir: Vec3) -> Frame {
+        let up = dir.normalize();
+        Frame::aligned(up, up)
+    }
+
+    /// Frame at the given direction, aligned with the given (terrain) normal.
+    ///
+    /// The north axis is the direction of the sphere north projected on the plane
+    /// orthogonal to the normal.
+    pub fn aligned(dir: Vec3, normal: Vec3) -> Frame {
+        let up = normal.normalize();
+        let north = sphere_north(dir.normalize());
+
+        let north = (north - up * up.dot(north)).normalize();
+        let east = north.cross(up);
+
+        Frame {
+            east,
+            north,
+            up
+        }
+    }
+
+    /// Frame at the given position, aligned with the sphere normal.
+    pub fn at(p: &Position<f32>) -> Frame {
+        Frame::new(p.as_unit().into())
+    }
+
+    /// Local to world rotation matrix.
+    ///
+    /// The `x`, `y` and `z` local axes are respectively mapped to east, north and up.
+    pub fn matrix(&self) -> Mat3 {
+        Mat3::from_cols(self.east, self.north, self.up)
+    }
+
+    /// Local to world rotation.
+    pub fn rotation(&self) -> Quat {
+        Quat::from_rotation_mat3(&self.matrix())
+    }
+
+    /// Local to world transformation, with the local origin at `origin`.
+    pub fn transformation(&self, origin: Vec3) -> Mat4 {
+        Mat4::from_rotation_translation(self.rotation(), origin)
+    }
+
+    /// Horizontal direction of the given heading (in radians, clockwise from the north).
+    pub fn heading(&self, heading: f32) -> Vec3 {
+        self.north * heading.cos() + self.east * heading.sin()
+    }
+}
+
+/// Direction of the north, tangent to the unit sphere at `up`.
+///
+/// At the poles, where it is undefined, it is the one given by a null azimuth.
+fn sphere_north(up: Vec3) -> Vec3 {
+    let north = Vec3::unit_z() - up * up.z;
+    if north.length_squared() > 1e-12 {
+        north.normalize()
+    } else {
+        Vec3::new(-up.z.signum(), 0.0, 0.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Frame;
+    use crate::location::Position;
+
+    #[test]
+    fn matches_position() {
+        let p = Position::new(1.2f32, -2.3);
+        let frame = Frame::at(&p);
+        assert!((frame.north - p.north().into()).length() < 1e-5);
+        assert!((frame.east - p.east().into()).length() < 1e-5);
+
+        let pole = Frame::at(&Position::new(0.0f32, 0.0));
+        assert!((pole.north - Position::new(0.0f32, 0.0).north().into()).length() < 1e-5);
+    }
+}
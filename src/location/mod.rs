@@ -0,0 +1,258 @@
+//! Locations
+//!
+//! Coordinate systems used to locate things on (and around) an object,
+//! with the minimal numeric tooling they rely on.
+
+use std::ops::{Mul, Div, Add, Sub, Neg};
+
+pub mod spherical;
+pub mod frame;
+pub mod polygon;
+pub mod projection;
+
+pub use spherical::Position;
+pub use frame::Frame;
+pub use polygon::Polygon;
+pub use projection::Projection;
+
+/// Multiplicative identity.
+pub trait One {
+    const ONE: Self;
+}
+
+/// Additive identity.
+pub trait Zero {
+    const ZERO: Self;
+}
+
+/// Trigonometric functions.
+pub trait Trigonometric {
+    type Output;
+
+    const PI: Self;
+
+    fn sin(self) -> Self::Output;
+    fn cos(self) -> Self::Output;
+    fn tan(self) -> Self::Output;
+    fn asin(self) -> Self::Output;
+    fn acos(self) -> Self::Output;
+    fn atan(self) -> Self::Output;
+    fn atan2(self, other: Self) -> Self::Output;
+}
+
+/// Scalar norm.
+pub trait Norm {
+    type Output;
+
+    fn abs(self) -> Self::Output;
+    fn sqrt(self) -> Self::Output;
+}
+
+/// Exponential and logarithm.
+pub trait Exponential {
+    type Output;
+
+    fn exp(self) -> Self::Output;
+    fn ln(self) -> Self::Output;
+}
+
+/// Real numbers.
+pub trait Real: Copy + PartialOrd + One + Zero + Trigonometric<Output = Self> + Norm<Output = Self> + Exponential<Output = Self> + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + Div<Output = Self> + Neg<Output = Self> {
+    fn from_f64(v: f64) -> Self;
+
+    fn to_f64(self) -> f64;
+}
+
+macro_rules! real {
+    ($t:ident) => {
+        impl One for $t {
+            const ONE: $t = 1.0;
+        }
+
+        impl Zero for $t {
+            const ZERO: $t = 0.0;
+        }
+
+        impl Trigonometric for $t {
+            type Output = $t;
+
+            const PI: $t = std::$t::consts::PI;
+
+            fn sin(self) -> $t { $t::sin(self) }
+            fn cos(self) -> $t { $t::cos(self) }
+            fn tan(self) -> $t { $t::tan(self) }
+            fn asin(self) -> $t { $t::asin(self) }
+            fn acos(self) -> $t { $t::acos(self) }
+            fn atan(self) -> $t { $t::atan(self) }
+            fn atan2(self, other: $t) -> $t { $t::atan2(self, other) }
+        }
+
+        impl Norm for $t {
+            type Output = $t;
+
+            fn abs(self) -> $t { $t::abs(self) }
+            fn sqrt(self) -> $t { $t::sqrt(self) }
+        }
+
+        impl Exponential for $t {
+            type Output = $t;
+
+            fn exp(self) -> $t { $t::exp(self) }
+            fn ln(self) -> $t { $t::ln(self) }
+        }
+
+        impl Real for $t {
+            fn from_f64(v: f64) -> $t {
+                v as $t
+            }
+
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+        }
+    };
+}
+
+real!(f32);
+real!(f64);
+
+/// 2D vector.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Vector2d<T> {
+    pub x: T,
+    pub y: T
+}
+
+impl<T> Vector2d<T> {
+    pub fn new(x: T, y: T) -> Vector2d<T> {
+        Vector2d { x, y }
+    }
+}
+
+impl<T> Vector2d<T> where T: Copy + Norm<Output = T> + Mul<Output = T> + Add<Output = T> + Div<Output = T> {
+    /// Create a new unit vector with the direction of `(x, y)`.
+    pub fn unit(x: T, y: T) -> Vector2d<T> {
+        let len = (x * x + y * y).sqrt();
+        Vector2d {
+            x: x / len,
+            y: y / len
+        }
+    }
+
+    /// Scalar (dot) product.
+    pub fn scalar(&self, other: Vector2d<T>) -> T {
+        self.x * other.x + self.y * other.y
+    }
+
+    pub fn len(&self) -> T {
+        self.scalar(*self).sqrt()
+    }
+}
+
+/// 3D vector.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Vector3d<T> {
+    pub x: T,
+    pub y: T,
+    pub z: T
+}
+
+impl<T> Vector3d<T> {
+    pub fn new(x: T, y: T, z: T) -> Vector3d<T> {
+        Vector3d { x, y, z }
+    }
+}
+
+impl<T> Vector3d<T> where T: Copy + Norm<Output = T> + Mul<Output = T> + Add<Output = T> + Div<Output = T> {
+    /// Scalar (dot) product.
+    pub fn scalar(&self, other: Vector3d<T>) -> T {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn len(&self) -> T {
+        self.scalar(*self).sqrt()
+    }
+
+    /// Return the unit vector with the same direction.
+    pub fn normalize(&self) -> Vector3d<T> {
+        let len = self.len();
+        Vector3d {
+            x: self.x / len,
+            y: self.y / len,
+            z: self.z / len
+        }
+    }
+}
+
+impl<T> Add for Vector3d<T> where T: Add<Output = T> {
+    type Output = Vector3d<T>;
+
+    fn add(self, other: Vector3d<T>) -> Vector3d<T> {
+        Vector3d {
+            x: self.x + other.x,
+            y: self.y + other.y,
+            z: self.z + other.z
+        }
+    }
+}
+
+impl<T> Sub for Vector3d<T> where T: Sub<Output = T> {
+    type Output = Vector3d<T>;
+
+    fn sub(self, other: Vector3d<T>) -> Vector3d<T> {
+        Vector3d {
+            x: self.x - other.x,
+            y: self.y - other.y,
+            z: self.z - other.z
+        }
+    }
+}
+
+impl<T> Neg for Vector3d<T> where T: Neg<Output = T> {
+    type Output = Vector3d<T>;
+
+    fn neg(self) -> Vector3d<T> {
+        Vector3d {
+            x: -self.x,
+            y: -self.y,
+            z: -self.z
+        }
+    }
+}
+
+impl<T> Mul<T> for Vector3d<T> where T: Copy + Mul<Output = T> {
+    type Output = Vector3d<T>;
+
+    fn mul(self, f: T) -> Vector3d<T> {
+        Vector3d {
+            x: self.x * f,
+            y: self.y * f,
+            z: self.z * f
+        }
+    }
+}
+
+/// Vector (cross) product.
+impl<T> Mul for Vector3d<T> where T: Copy + Mul<Output = T> + Sub<Output = T> {
+    type Output = Vector3d<T>;
+
+    fn mul(self, other: Vector3d<T>) -> Vector3d<T> {
+        Vector3d {
+            x: self.y * other.z - self.z * other.y,
+            y: self.z * other.x - self.x * other.z,
+            z: self.x * other.y - self.y * other.x
+        }
+    }
+}
+
+impl From<Vector3d<f32>> for glam::Vec3 {
+    fn from(v: Vector3d<f32>) -> glam::Vec3 {
+        glam::Vec3::new(v.x, v.y, v.z)
+    }
+}
+
+impl From<glam::Vec3> for Vector3d<f32> {
+    fn from(v: glam::Vec3) -> Vector3d<f32> {
+        Vector3d::new(v.x, v.y, v.z)
+    }
+}
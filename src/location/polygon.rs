@@ -0,0 +1,387 @@
+use super::{Vector3d, Position, Real};
+use crate::topology::{Cell, Cap};
+
+/// Spherical polygon.
+///
+/// Vertices are connected by great-circle arcs (shorter than PI),
+/// and the inside of the polygon is on the left of its edges
+/// (that is to say, vertices are given counter-clockwise when seen from outside the sphere).
+/// Polygons are expected to be simple (no self intersections).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Polygon<T> {
+    vertices: Vec<Position<T>>
+}
+
+impl<T> Polygon<T> {
+    pub fn new(vertices: Vec<Position<T>>) -> Polygon<T> {
+        Polygon {
+            vertices
+        }
+    }
+
+    pub fn vertices(&self) -> &[Position<T>] {
+        &self.vertices
+    }
+
+    /// Return the complementary polygon (same edges, opposite orientation).
+    pub fn reversed(&self) -> Polygon<T> where T: Copy {
+        Polygon::new(self.vertices.iter().rev().cloned().collect())
+    }
+}
+
+impl<T: Real> Polygon<T> {
+    fn units(&self) -> Vec<Vector3d<T>> {
+        self.vertices.iter().map(|p| p.as_unit()).collect()
+    }
+
+    /// Signed area of the polygon on the unit sphere, in steradians.
+    ///
+    /// It is positive for counter-clockwise polygons, negative for clockwise polygons.
+    pub fn area(&self) -> T {
+        let v = self.units();
+        let mut area = T::ZERO;
+        for i in 1..(v.len().max(2) - 1) {
+            area = area + triangle_area(v[0], v[i], v[i + 1]);
+        }
+
+        area
+    }
+
+    /// Checks if the given position is inside the polygon.
+    pub fn contains(&self, p: &Position<T>) -> bool {
+        let v = self.units();
+        if v.len() < 3 {
+            return false
+        }
+
+        // A point just on the left of the first edge is inside the polygon.
+        let eps = T::from_f64(1e-4);
+        let half = T::from_f64(0.5);
+        let inside = ((v[0] + v[1]) * half + (v[0] * v[1]) * eps).normalize();
+        let p = p.as_unit();
+
+        let mut crossings = 0;
+        for i in 0..v.len() {
+            if arcs_intersection(p, inside, v[i], v[(i + 1) % v.len()]).is_some() {
+                crossings += 1
+            }
+        }
+
+        crossings % 2 == 0
+    }
+
+    /// Intersection of two polygons.
+    pub fn intersection(&self, other: &Polygon<T>) -> Vec<Polygon<T>> {
+        self.clip(other, false)
+    }
+
+    /// Union of two polygons.
+    pub fn union(&self, other: &Polygon<T>) -> Vec<Polygon<T>> {
+        self.clip(other, true)
+    }
+
+    /// Greiner-Hormann clipping.
+    ///
+    /// Degenerate cases (a vertex of a polygon lying on an edge of the other) are not handled.
+    fn clip(&self, other: &Polygon<T>, union: bool) -> Vec<Polygon<T>> {
+        let a = self.units();
+        let b = other.units();
+
+        // Find the intersections between the edges of both polygons.
+        let mut intersections = Vec::new();
+        for i in 0..a.len() {
+            for j in 0..b.len() {
+                if let Some((x, alpha, beta)) = arcs_intersection(a[i], a[(i + 1) % a.len()], b[j], b[(j + 1) % b.len()]) {
+                    intersections.push((i, alpha, j, beta, x))
+                }
+            }
+        }
+
+        if intersections.is_empty() {
+            let a_in_b = other.contains(&self.vertices[0]);
+            let b_in_a = self.contains(&other.vertices[0]);
+            return match (union, a_in_b, b_in_a) {
+                (false, true, _) => vec![self.clone()],
+                (false, false, true) => vec![other.clone()],
+                (false, false, false) => Vec::new(),
+                (true, true, _) => vec![other.clone()],
+                (true, false, true) => vec![self.clone()],
+                (true, false, false) => vec![self.clone(), other.clone()]
+            }
+        }
+
+        // Build the two vertex lists, with the intersections inserted.
+        let mut nodes = Vec::new();
+        let a_intersections = build_list(&mut nodes, &a, &intersections, |&(i, alpha, _, _, _)| (i, alpha));
+        let a_len = nodes.len();
+        let b_intersections = build_list(&mut nodes, &b, &intersections, |&(_, _, j, beta, _)| (j, beta));
+        for k in 0..intersections.len() {
+            nodes[a_intersections[k]].neighbor = b_intersections[k];
+            nodes[b_intersections[k]].neighbor = a_intersections[k];
+        }
+
+        // Mark entry and exit points.
+        let mut entry = !other.contains(&self.vertices[0]) ^ union;
+        for node in &mut nodes[0..a_len] {
+            if node.intersection {
+                node.entry = entry;
+                entry = !entry
+            }
+        }
+
+        let mut entry = !self.contains(&other.vertices[0]) ^ union;
+        for node in &mut nodes[a_len..] {
+            if node.intersection {
+                node.entry = entry;
+                entry = !entry
+            }
+        }
+
+        // Trace the resulting polygons.
+        // Starting from entry points ensures that the polygons keep their orientation.
+        let mut result = Vec::new();
+        while let Some(start) = (0..a_len).find(|&k| nodes[k].intersection && nodes[k].entry && !nodes[k].visited) {
+            let mut vertices = vec![nodes[start].position.into()];
+            let mut current = start;
+            loop {
+                nodes[current].visited = true;
+                let n = nodes[current].neighbor;
+                nodes[n].visited = true;
+
+                let forward = nodes[current].entry;
+                loop {
+                    current = if forward { nodes[current].next } else { nodes[current].prev };
+                    if current == start || nodes[current].neighbor == start {
+                        break
+                    }
+
+                    vertices.push(nodes[current].position.into());
+                    if nodes[current].intersection {
+                        break
+                    }
+                }
+
+                if current == start || nodes[current].neighbor == start {
+                    break
+                }
+
+                current = nodes[current].neighbor;
+            }
+
+            result.push(Polygon::new(vertices))
+        }
+
+        result
+    }
+}
+
+impl Polygon<f32> {
+    /// Cells of the given depth whose center is inside the polygon.
+    pub fn cells(&self, depth: u32) -> Vec<Cell> {
+        let v: Vec<glam::Vec3> = self.units().into_iter().map(|v| v.into()).collect();
+
+        // Bounding cap of the polygon, if it is small enough to be useful.
+        let mut bounds = None;
+        let area = self.area();
+        if area > 0.0 && area < std::f32::consts::PI * 2.0 {
+            let cap = Cap::from_points(&v, 1.0, 1.0);
+            if cap.angle < std::f32::consts::FRAC_PI_2 {
+                bounds = Some(cap)
+            }
+        }
+
+        let mut cells = Vec::new();
+        let mut stack: Vec<Cell> = Cell::all(0).collect();
+        while let Some(cell) = stack.pop() {
+            if let Some(bounds) = &bounds {
+                if !bounds.intersects(&cell.cap(1.0, 1.0)) {
+                    continue
+                }
+            }
+
+            if cell.depth() < depth {
+                stack.extend_from_slice(&cell.children())
+            } else if self.contains(&Vector3d::from(cell.center()).into()) {
+                cells.push(cell)
+            }
+        }
+
+        cells.sort();
+        cells
+    }
+}
+
+/// Signed area of the spherical triangle `abc`.
+fn triangle_area<T: Real>(a: Vector3d<T>, b: Vector3d<T>, c: Vector3d<T>) -> T {
+    let two = T::ONE + T::ONE;
+    let det = a.scalar(b * c);
+    let div = T::ONE + a.scalar(b) + b.scalar(c) + c.scalar(a);
+    two * det.atan2(div)
+}
+
+/// Intersection of the arcs `a0 a1` and `b0 b1`.
+///
+/// Return the intersection point, and its relative position on both arcs.
+fn arcs_intersection<T: Real>(a0: Vector3d<T>, a1: Vector3d<T>, b0: Vector3d<T>, b1: Vector3d<T>) -> Option<(Vector3d<T>, T, T)> {
+    let na = a0 * a1;
+    let nb = b0 * b1;
+    let i = na * nb;
+    if i.len() <= T::from_f64(1e-12) {
+        return None
+    }
+
+    let i = i.normalize();
+    for x in [i, -i].iter() {
+        if let (Some(alpha), Some(beta)) = (arc_position(a0, a1, na, *x), arc_position(b0, b1, nb, *x)) {
+            return Some((*x, alpha, beta))
+        }
+    }
+
+    None
+}
+
+/// Relative position of `x` on the arc `a b` (with normal `n`), if `x` is on the arc.
+fn arc_position<T: Real>(a: Vector3d<T>, b: Vector3d<T>, n: Vector3d<T>, x: Vector3d<T>) -> Option<T> {
+    if (a * x).scalar(n) >= T::ZERO && (x * b).scalar(n) >= T::ZERO {
+        let ax = (a * x).len().atan2(a.scalar(x));
+        let ab = (a * b).len().atan2(a.scalar(b));
+        Some(ax / ab)
+    } else {
+        None
+    }
+}
+
+struct Node<T> {
+    position: Vector3d<T>,
+    next: usize,
+    prev: usize,
+    intersection: bool,
+    entry: bool,
+    neighbor: usize,
+    visited: bool
+}
+
+/// Append a circular vertex list to `nodes`, with the given intersections inserted,
+/// and return the node index of each intersection.
+fn build_list<T: Real, I, F>(nodes: &mut Vec<Node<T>>, vertices: &[Vector3d<T>], intersections: &[(usize, T, usize, T, Vector3d<T>)], edge: F) -> Vec<usize> where F: Fn(&(usize, T, usize, T, Vector3d<T>)) -> (usize, I), I: PartialOrd {
+    let offset = nodes.len();
+    let mut indexes = vec![0; intersections.len()];
+
+    for (i, v) in vertices.iter().enumerate() {
+        nodes.push(Node {
+            position: *v,
+            next: 0,
+            prev: 0,
+            intersection: false,
+            entry: false,
+            neighbor: 0,
+            visited: false
+        });
+
+        let mut on_edge: Vec<usize> = (0..intersections.len()).filter(|&k| edge(&intersections[k]).0 == i).collect();
+        on_edge.sort_by(|&k, &l| edge(&intersections[k]).1.partial_cmp(&edge(&intersections[l]).1).unwrap());
+        for k in on_edge {
+            indexes[k] = nodes.len();
+            nodes.push(Node {
+                position: intersections[k].4,
+                next: 0,
+                prev: 0,
+                intersection: true,
+                entry: false,
+                neighbor: 0,
+                visited: false
+            })
+        }
+    }
+
+    let len = nodes.len() - offset;
+    for k in 0..len {
+        nodes[offset + k].next = offset + (k + 1) % len;
+        nodes[offset + k].prev = offset + (k + len - 1) % len;
+    }
+
+    indexes
+}
+
+#[cfg(test)]
+mod test {
+    use super::Polygon;
+    use crate::location::Position;
+    use std::f64::consts::PI;
+
+    /// Spherical "square" centered on the equator.
+    fn square(azimuth: f64, size: f64) -> Polygon<f64> {
+        let e = PI / 2.0;
+        Polygon::new(vec![
+            Position::new(e + size, azimuth - size),
+            Position::new(e + size, azimuth + size),
+            Position::new(e - size, azimuth + size),
+            Position::new(e - size, azimuth - size)
+        ])
+    }
+
+    #[test]
+    fn area() {
+        let octant = Polygon::new(vec![
+            Position::new(PI / 2.0, 0.0),
+            Position::new(PI / 2.0, PI / 2.0),
+            Position::new(0.0, 0.0)
+        ]);
+        assert!((octant.area() - PI / 2.0).abs() < 1e-9);
+        assert!((octant.reversed().area() + PI / 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn contains() {
+        let s = square(0.0, 0.1);
+        assert!(s.contains(&Position::new(PI / 2.0, 0.0)));
+        assert!(!s.contains(&Position::new(PI / 2.0, 0.2)));
+        assert!(!s.reversed().contains(&Position::new(PI / 2.0, 0.0)));
+        assert!(s.reversed().contains(&Position::new(PI / 2.0, 2.0)));
+    }
+
+    #[test]
+    fn clipping() {
+        let e = PI / 2.0;
+        let a = square(0.0, 0.1);
+        let b = Polygon::new(vec![
+            Position::new(e + 0.1, 0.15),
+            Position::new(e, 0.25),
+            Position::new(e - 0.1, 0.15),
+            Position::new(e, 0.05)
+        ]);
+
+        let i = a.intersection(&b);
+        assert_eq!(i.len(), 1);
+        assert_eq!(i[0].vertices().len(), 3);
+        assert!((i[0].area() - 0.0025).abs() < 1e-4);
+
+        let u = a.union(&b);
+        assert_eq!(u.len(), 1);
+        assert_eq!(u[0].vertices().len(), 9);
+        assert!((u[0].area() - (a.area() + b.area() - i[0].area())).abs() < 1e-9);
+
+        assert!(a.intersection(&square(1.0, 0.1)).is_empty());
+        assert_eq!(a.union(&square(1.0, 0.1)).len(), 2);
+        assert_eq!(a.intersection(&square(0.0, 0.2)), vec![a.clone()]);
+    }
+
+    #[test]
+    fn cells() {
+        use std::f32::consts::PI;
+        use crate::topology::Cell;
+
+        let octant = Polygon::new(vec![
+            Position::new(PI / 2.0, 0.0),
+            Position::new(PI / 2.0, PI / 2.0),
+            Position::new(0.0, 0.0)
+        ]);
+
+        let cells = octant.cells(3);
+        let expected = Cell::count(3) as usize / 8;
+        assert!(cells.len() > expected * 9 / 10 && cells.len() < expected * 11 / 10);
+        for cell in cells {
+            assert!(cell.center().x >= 0.0 && cell.center().y >= 0.0 && cell.center().z >= 0.0)
+        }
+    }
+}
@@ -0,0 +1,372 @@
+//! Map projections.
+//!
+//! Projections map spherical positions to points of a 2D plane (and back),
+//! so that data living on a sphere can be painted or imported as images.
+
+use std::io;
+use glam::{Vec2, Vec3};
+use super::{Vector2d, Vector3d, Position, Real};
+use crate::topology::sphere::icosahedron_vertices;
+
+pub trait Projection<T> {
+    /// Project the given position on the plane.
+    fn forward(&self, p: &Position<T>) -> Vector2d<T>;
+
+    /// Find the position projected on the given point, if any.
+    fn inverse(&self, v: Vector2d<T>) -> Option<Position<T>>;
+
+    /// Bounding rectangle of the projected sphere, as `(min, max)`.
+    fn bounds(&self) -> (Vector2d<T>, Vector2d<T>);
+}
+
+/// Equirectangular projection.
+///
+/// `x` is the longitude, `y` the latitude.
+pub struct Equirectangular;
+
+impl<T: Real> Projection<T> for Equirectangular {
+    fn forward(&self, p: &Position<T>) -> Vector2d<T> {
+        Vector2d::new(wrap(p.longitude()), p.latitude())
+    }
+
+    fn inverse(&self, v: Vector2d<T>) -> Option<Position<T>> {
+        let half_pi = T::PI / (T::ONE + T::ONE);
+        if v.x < -T::PI || v.x > T::PI || v.y < -half_pi || v.y > half_pi {
+            None
+        } else {
+            Some(Position::from_geographic(v.y, v.x))
+        }
+    }
+
+    fn bounds(&self) -> (Vector2d<T>, Vector2d<T>) {
+        let half_pi = T::PI / (T::ONE + T::ONE);
+        (Vector2d::new(-T::PI, -half_pi), Vector2d::new(T::PI, half_pi))
+    }
+}
+
+/// Mercator projection.
+///
+/// The poles are projected at infinity,
+/// so the projection is limited to the given maximum latitude.
+pub struct Mercator<T> {
+    pub max_latitude: T
+}
+
+impl<T: Real> Mercator<T> {
+    pub fn new(max_latitude: T) -> Mercator<T> {
+        Mercator {
+            max_latitude
+        }
+    }
+
+    fn y(&self, latitude: T) -> T {
+        let half = T::from_f64(0.5);
+        let s = latitude.sin();
+        half * ((T::ONE + s) / (T::ONE - s)).ln()
+    }
+}
+
+impl<T: Real> Projection<T> for Mercator<T> {
+    fn forward(&self, p: &Position<T>) -> Vector2d<T> {
+        let mut latitude = p.latitude();
+        if latitude > self.max_latitude {
+            latitude = self.max_latitude
+        } else if latitude < -self.max_latitude {
+            latitude = -self.max_latitude
+        }
+
+        Vector2d::new(wrap(p.longitude()), self.y(latitude))
+    }
+
+    fn inverse(&self, v: Vector2d<T>) -> Option<Position<T>> {
+        let max_y = self.y(self.max_latitude);
+        if v.x < -T::PI || v.x > T::PI || v.y < -max_y || v.y > max_y {
+            None
+        } else {
+            let half = T::from_f64(0.5);
+            let sinh = (v.y.exp() - (-v.y).exp()) * half;
+            Some(Position::from_geographic(sinh.atan(), v.x))
+        }
+    }
+
+    fn bounds(&self) -> (Vector2d<T>, Vector2d<T>) {
+        let max_y = self.y(self.max_latitude);
+        (Vector2d::new(-T::PI, -max_y), Vector2d::new(T::PI, max_y))
+    }
+}
+
+/// Longitude wrapped in `[-PI, PI]`.
+fn wrap<T: Real>(longitude: T) -> T {
+    longitude.sin().atan2(longitude.cos())
+}
+
+/// Lambert azimuthal equal-area projection.
+///
+/// The whole sphere (of radius 1) is projected in the disk of radius 2
+/// around the projection center, with the north up.
+pub struct LambertAzimuthal<T> {
+    center: Position<T>
+}
+
+impl<T> LambertAzimuthal<T> {
+    pub fn new(center: Position<T>) -> LambertAzimuthal<T> {
+        LambertAzimuthal {
+            center
+        }
+    }
+}
+
+impl<T: Real> Projection<T> for LambertAzimuthal<T> {
+    fn forward(&self, p: &Position<T>) -> Vector2d<T> {
+        let p = p.as_unit();
+        let up = self.center.as_unit();
+        let cos = p.scalar(up);
+        if cos <= -T::ONE {
+            // The antipode is projected on the whole border of the disk.
+            return Vector2d::new(T::ZERO, T::ONE + T::ONE)
+        }
+
+        let k = ((T::ONE + T::ONE) / (T::ONE + cos)).sqrt();
+        Vector2d::new(k * p.scalar(self.center.east()), k * p.scalar(self.center.north()))
+    }
+
+    fn inverse(&self, v: Vector2d<T>) -> Option<Position<T>> {
+        let two = T::ONE + T::ONE;
+        let rho = (v.x * v.x + v.y * v.y).sqrt();
+        if rho > two {
+            None
+        } else if rho == T::ZERO {
+            Some(self.center)
+        } else {
+            let c = two * (rho / two).asin();
+            let dir = self.center.east() * (v.x / rho) + self.center.north() * (v.y / rho);
+            let p: Vector3d<T> = self.center.as_unit() * c.cos() + dir * c.sin();
+            Some(p.into())
+        }
+    }
+
+    fn bounds(&self) -> (Vector2d<T>, Vector2d<T>) {
+        let two = T::ONE + T::ONE;
+        (Vector2d::new(-two, -two), Vector2d::new(two, two))
+    }
+}
+
+/// Icosahedral net projection (Dymaxion-style).
+///
+/// The sphere is projected (gnomonically) on the faces of the icosahedron,
+/// which are then unfolded as a strip of 20 unit triangles:
+///
+/// ```text
+///  /\  /\  /\  /\  /\
+/// /__\/__\/__\/__\/__\
+/// \  /\  /\  /\  /\  /\
+///  \/__\/__\/__\/__\/__\
+///   \  /\  /\  /\  /\  /
+///    \/  \/  \/  \/  \/
+/// ```
+pub struct Icosahedral {
+    /// Vertices of each unfolded triangle, on the sphere and on the plane.
+    triangles: Vec<([Vec3; 3], [Vec2; 3])>
+}
+
+/// Vertices of the icosahedron around the vertex 0, in order.
+const UPPER: [usize; 5] = [11, 5, 1, 7, 10];
+
+/// Vertices of the icosahedron around the vertex 3, in order.
+const LOWER: [usize; 5] = [4, 9, 8, 6, 2];
+
+impl Icosahedral {
+    pub fn new() -> Icosahedral {
+        let v = icosahedron_vertices();
+        let h = 3.0f32.sqrt() / 2.0;
+        let mut triangles = Vec::new();
+
+        for i in 0..5 {
+            let j = (i + 1) % 5;
+            let x = i as f32;
+
+            triangles.push((
+                [v[0], v[UPPER[i]], v[UPPER[j]]],
+                [Vec2::new(x + 0.5, 2.0 * h), Vec2::new(x, h), Vec2::new(x + 1.0, h)]
+            ));
+            triangles.push((
+                [v[UPPER[i]], v[UPPER[j]], v[LOWER[i]]],
+                [Vec2::new(x, h), Vec2::new(x + 1.0, h), Vec2::new(x + 0.5, 0.0)]
+            ));
+            triangles.push((
+                [v[LOWER[i]], v[LOWER[j]], v[UPPER[j]]],
+                [Vec2::new(x + 0.5, 0.0), Vec2::new(x + 1.5, 0.0), Vec2::new(x + 1.0, h)]
+            ));
+            triangles.push((
+                [v[3], v[LOWER[i]], v[LOWER[j]]],
+                [Vec2::new(x + 1.0, -h), Vec2::new(x + 0.5, 0.0), Vec2::new(x + 1.5, 0.0)]
+            ));
+        }
+
+        Icosahedral {
+            triangles
+        }
+    }
+}
+
+impl Default for Icosahedral {
+    fn default() -> Icosahedral {
+        Icosahedral::new()
+    }
+}
+
+impl Projection<f32> for Icosahedral {
+    fn forward(&self, p: &Position<f32>) -> Vector2d<f32> {
+        let dir: Vec3 = p.as_unit().into();
+
+        // Find the face with the most central projection of the position.
+        let mut best = (f32::NEG_INFINITY, Vec2::zero());
+        for ([a, b, c], [a2, b2, c2]) in &self.triangles {
+            let n = (*b - *a).cross(*c - *a);
+            let t = a.dot(n) / dir.dot(n);
+            if t <= 0.0 {
+                continue
+            }
+
+            let q = dir * t;
+            let u = (*c - *b).cross(q - *b).dot(n);
+            let v = (*a - *c).cross(q - *c).dot(n);
+            let w = (*b - *a).cross(q - *a).dot(n);
+            let score = u.min(v).min(w);
+            if score > best.0 {
+                let sum = u + v + w;
+                best = (score, (*a2 * u + *b2 * v + *c2 * w) / sum)
+            }
+        }
+
+        Vector2d::new(best.1.x, best.1.y)
+    }
+
+    fn inverse(&self, v: Vector2d<f32>) -> Option<Position<f32>> {
+        let q = Vec2::new(v.x, v.y);
+        for ([a, b, c], [a2, b2, c2]) in &self.triangles {
+            let det = perp_dot(*b2 - *a2, *c2 - *a2);
+            let u = perp_dot(*c2 - *b2, q - *b2) / det;
+            let v = perp_dot(*a2 - *c2, q - *c2) / det;
+            let w = perp_dot(*b2 - *a2, q - *a2) / det;
+            if u >= -1e-6 && v >= -1e-6 && w >= -1e-6 {
+                let p = *a * u + *b * v + *c * w;
+                return Some(Vector3d::from(p).into())
+            }
+        }
+
+        None
+    }
+
+    fn bounds(&self) -> (Vector2d<f32>, Vector2d<f32>) {
+        let h = 3.0f32.sqrt() / 2.0;
+        (Vector2d::new(0.0, -h), Vector2d::new(5.5, 2.0 * h))
+    }
+}
+
+fn perp_dot(a: Vec2, b: Vec2) -> f32 {
+    a.x * b.y - a.y * b.x
+}
+
+/// Raster image of a projection.
+///
+/// Maps the pixels of a `width`x`height` image covering the projection bounds to positions
+/// (and back). Pixels are in row-major order, starting from the top-left corner.
+pub struct Raster<P> {
+    pub projection: P,
+    pub width: usize,
+    pub height: usize
+}
+
+impl<P> Raster<P> {
+    pub fn new(projection: P, width: usize, height: usize) -> Raster<P> {
+        Raster {
+            projection,
+            width,
+            height
+        }
+    }
+
+    /// Pixel containing the given position.
+    pub fn pixel<T: Real>(&self, p: &Position<T>) -> (usize, usize) where P: Projection<T> {
+        let (min, max) = self.projection.bounds();
+        let v = self.projection.forward(p);
+        let x = ((v.x - min.x) / (max.x - min.x)).to_f64() * self.width as f64;
+        let y = ((max.y - v.y) / (max.y - min.y)).to_f64() * self.height as f64;
+        (
+            (x.max(0.0) as usize).min(self.width - 1),
+            (y.max(0.0) as usize).min(self.height - 1)
+        )
+    }
+
+    /// Position at the center of the given pixel, if any.
+    pub fn position<T: Real>(&self, x: usize, y: usize) -> Option<Position<T>> where P: Projection<T> {
+        let (min, max) = self.projection.bounds();
+        let fx = T::from_f64((x as f64 + 0.5) / self.width as f64);
+        let fy = T::from_f64((y as f64 + 0.5) / self.height as f64);
+        self.projection.inverse(Vector2d::new(min.x + (max.x - min.x) * fx, max.y - (max.y - min.y) * fy))
+    }
+
+    /// Render an image by evaluating `f` at the center of each pixel.
+    ///
+    /// Pixels out of the projected sphere are set to `background`.
+    pub fn render<T: Real, V: Clone, F: FnMut(&Position<T>) -> V>(&self, background: V, mut f: F) -> Vec<V> where P: Projection<T> {
+        let mut pixels = Vec::with_capacity(self.width * self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                match self.position(x, y) {
+                    Some(p) => pixels.push(f(&p)),
+                    None => pixels.push(background.clone())
+                }
+            }
+        }
+
+        pixels
+    }
+
+    /// Sample an image at the given position.
+    pub fn sample<'a, T: Real, V>(&self, pixels: &'a [V], p: &Position<T>) -> &'a V where P: Projection<T> {
+        let (x, y) = self.pixel(p);
+        &pixels[x + y * self.width]
+    }
+}
+
+/// Write an RGB image in the (binary) PPM format.
+pub fn write_ppm<W: io::Write>(out: &mut W, width: usize, height: usize, pixels: &[[u8; 3]]) -> io::Result<()> {
+    write!(out, "P6\n{} {}\n255\n", width, height)?;
+    for p in pixels {
+        out.write_all(p)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn round_trip<T: Real + std::fmt::Debug, P: Projection<T>>(projection: &P, epsilon: f64) {
+        for i in 1..10 {
+            for j in 0..40 {
+                let p = Position::new(T::from_f64(i as f64 * 0.31), T::from_f64(j as f64 * 0.31 - 6.0));
+                let q = projection.inverse(projection.forward(&p)).unwrap();
+                assert!(p.distance(&q).to_f64() < epsilon, "{:?} != {:?}", p, q)
+            }
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        round_trip::<f64, _>(&Equirectangular, 1e-9);
+        round_trip(&Mercator::new(1.5f64), 1e-9);
+        round_trip(&LambertAzimuthal::new(Position::new(1.0f64, 0.5)), 1e-6);
+        round_trip(&Icosahedral::new(), 1e-5);
+    }
+
+    #[test]
+    fn raster() {
+        let raster = Raster::new(Equirectangular, 360, 180);
+        let p: Position<f32> = raster.position(10, 20).unwrap();
+        assert_eq!(raster.pixel(&p), (10, 20));
+    }
+}
@@ -1,6 +1,11 @@
-use super::{Vector2d, Vector3d, Trigonometric, Norm, One};
+use super::{Vector3d, Trigonometric, Norm, One, Real};
 use std::ops::{Mul, Div, Add, Sub};
 
+/// Spherical position.
+///
+/// The polar angle is measured from the `z` axis (the north pole),
+/// and the azimuth from the `x` axis toward the `y` axis.
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Position<T> {
     polar: T,
     azimuth: T
@@ -9,12 +14,22 @@ pub struct Position<T> {
 impl<T> Position<T> {
     pub fn new(polar: T, azimuth: T) -> Position<T> {
         Position {
-            polar: polar,
-            azimuth: azimuth
+            polar,
+            azimuth
         }
     }
 }
 
+impl<T: Copy> Position<T> {
+    pub fn polar(&self) -> T {
+        self.polar
+    }
+
+    pub fn azimuth(&self) -> T {
+        self.azimuth
+    }
+}
+
 impl<T> Position<T> where T: Copy + Trigonometric<Output = T> + Mul<Output = T> + One {
     /// Return this spherical position as a cartesian position.
     pub fn as_vector(&self, len: T) -> Vector3d<T> {
@@ -37,22 +52,7 @@ impl<T> Position<T> where T: Copy + Trigonometric<Output = T> + One + Norm<Outpu
         let a = self.as_vector(T::ONE);
         let b = other.as_vector(T::ONE);
 
-        (a*b).len()/a.scalar(b)
-    }
-
-    /// Move in the direction of the given other point by the given factor
-    /// (0 -> don't move, 1 -> move all the way, 0.5 -> between the two points, etc.).
-    pub fn move_to(&self, other: &Position<T>, f: T) -> Position<T>
-    {
-        let angle = self.distance(other);
-        let x = Vector2d::unit(T::ONE - angle.cos(), angle.sin());
-
-        let f_angle = f * angle;
-        let y = Vector2d::unit(T::ONE - f_angle.cos(), f_angle.sin());
-
-        let k = x.scalar(y);
-        let p = self.as_unit()* (T::ONE - k) + other.as_unit()*k;
-        p.into()
+        (a*b).len().atan2(a.scalar(b))
     }
 
     /// Semanticaly equivalent to `move_to(other, 0.5)`, but faster.
@@ -70,11 +70,231 @@ impl<T> Position<T> where T: Copy + Trigonometric<Output = T> + One + Norm<Outpu
     }
 }
 
-impl<T> From<Vector3d<T>> for Position<T> where T: Copy + Trigonometric<Output = T> + Norm<Output = T> + Div<Output = T> {
+/// Geographic coordinates.
+impl<T: Real> Position<T> {
+    /// Create a position from its latitude and longitude, in radians.
+    pub fn from_geographic(latitude: T, longitude: T) -> Position<T> {
+        Position::new(T::PI / (T::ONE + T::ONE) - latitude, longitude)
+    }
+
+    /// Latitude, from `-PI/2` (south pole) to `PI/2` (north pole).
+    pub fn latitude(&self) -> T {
+        T::PI / (T::ONE + T::ONE) - self.polar
+    }
+
+    /// Longitude (same as the azimuth).
+    pub fn longitude(&self) -> T {
+        self.azimuth
+    }
+}
+
+/// Great-circle navigation.
+///
+/// Bearings are given in radians, clockwise from the north,
+/// and distances are angular distances (multiply by the radius to get a length).
+impl<T: Real> Position<T> {
+    /// Unit vector pointing to the north, tangent to the sphere at this position.
+    pub fn north(&self) -> Vector3d<T> {
+        Vector3d {
+            x: -self.polar.cos() * self.azimuth.cos(),
+            y: -self.polar.cos() * self.azimuth.sin(),
+            z: self.polar.sin()
+        }
+    }
+
+    /// Unit vector pointing to the east, tangent to the sphere at this position.
+    pub fn east(&self) -> Vector3d<T> {
+        Vector3d {
+            x: -self.azimuth.sin(),
+            y: self.azimuth.cos(),
+            z: T::ZERO
+        }
+    }
+
+    /// Bearing of the given tangent direction at this position, in `[0, 2PI)`.
+    fn bearing_of(&self, t: Vector3d<T>) -> T {
+        let b = t.scalar(self.east()).atan2(t.scalar(self.north()));
+        if b < T::ZERO {
+            b + T::PI + T::PI
+        } else {
+            b
+        }
+    }
+
+    /// Initial bearing of the great-circle path going to `other`.
+    pub fn initial_bearing(&self, other: &Position<T>) -> T {
+        let p = self.as_unit();
+        let q = other.as_unit();
+        self.bearing_of(q - p * p.scalar(q))
+    }
+
+    /// Final bearing when reaching `other` along the great-circle path.
+    pub fn final_bearing(&self, other: &Position<T>) -> T {
+        let p = self.as_unit();
+        let q = other.as_unit();
+        other.bearing_of(q * q.scalar(p) - p)
+    }
+
+    /// Destination reached after travelling the given angular distance
+    /// along the great circle starting with the given bearing.
+    pub fn destination(&self, bearing: T, distance: T) -> Position<T> {
+        let dir = self.north() * bearing.cos() + self.east() * bearing.sin();
+        let v = self.as_unit() * distance.cos() + dir * distance.sin();
+        v.into()
+    }
+
+    /// Move in the direction of the given other point by the given factor
+    /// (0 -> don't move, 1 -> move all the way, 0.5 -> between the two points, etc.).
+    ///
+    /// Between antipodal points, where the path is undefined, move toward the north.
+    pub fn move_to(&self, other: &Position<T>, f: T) -> Position<T> {
+        let angle = self.distance(other);
+        let s = angle.sin();
+
+        if s.abs() <= T::from_f64(1e-12) {
+            return if angle < T::ONE {
+                *self
+            } else {
+                self.destination(T::ZERO, f * angle)
+            }
+        }
+
+        let a = ((T::ONE - f) * angle).sin() / s;
+        let b = (f * angle).sin() / s;
+
+        let p = self.as_unit() * a + other.as_unit() * b;
+        p.into()
+    }
+
+    /// Signed angular distance of this position to the great circle going from `start` to `end`.
+    ///
+    /// The distance is positive when on the right of the path, negative on the left.
+    /// If `start` and `end` are the same or antipodal, the great circle is undefined
+    /// and the (positive) distance to `start` is returned.
+    pub fn cross_track_distance(&self, start: &Position<T>, end: &Position<T>) -> T {
+        let n = start.as_unit() * end.as_unit();
+        if n.len() <= T::from_f64(1e-12) {
+            return self.distance(start)
+        }
+
+        -self.as_unit().scalar(n.normalize()).asin()
+    }
+
+    /// Intersections of the great circle passing through `a_start` and `a_end`
+    /// with the one passing through `b_start` and `b_end`.
+    ///
+    /// The two antipodal intersections are returned,
+    /// the one closest to `a_start` first.
+    /// Return `None` if the two great circles are the same.
+    pub fn intersection(a_start: &Position<T>, a_end: &Position<T>, b_start: &Position<T>, b_end: &Position<T>) -> Option<(Position<T>, Position<T>)> {
+        let na = a_start.as_unit() * a_end.as_unit();
+        let nb = b_start.as_unit() * b_end.as_unit();
+        let i = na * nb;
+
+        if i.len() <= T::from_f64(1e-12) * na.len() * nb.len() {
+            None
+        } else {
+            let i = i.normalize();
+            if i.scalar(a_start.as_unit()) >= T::ZERO {
+                Some((i.into(), (-i).into()))
+            } else {
+                Some(((-i).into(), i.into()))
+            }
+        }
+    }
+
+    /// Evenly spaced points along the great-circle path going to `other`.
+    ///
+    /// Both ends are included, so `count` must be at least 2.
+    pub fn waypoints(&self, other: &Position<T>, count: usize) -> Vec<Position<T>> {
+        assert!(count >= 2);
+        let last = T::from_f64((count - 1) as f64);
+        (0..count).map(|i| {
+            if i == 0 {
+                *self
+            } else if i == count - 1 {
+                *other
+            } else {
+                self.move_to(other, T::from_f64(i as f64) / last)
+            }
+        }).collect()
+    }
+}
+
+impl<T> From<Vector3d<T>> for Position<T> where T: Copy + Trigonometric<Output = T> + Norm<Output = T> + Mul<Output = T> + Add<Output = T> + Div<Output = T> {
     fn from(v: Vector3d<T>) -> Position<T> {
         Position {
             polar: T::acos(v.z/v.len()),
-            azimuth: T::atan(v.y/v.z)
+            azimuth: T::atan2(v.y, v.x)
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::Position;
+    use std::f64::consts::PI;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{} != {}", a, b)
+    }
+
+    #[test]
+    fn bearings() {
+        let p = Position::new(PI / 2.0, 0.0);
+        assert_close(p.initial_bearing(&Position::new(0.1, 0.0)), 0.0);
+        assert_close(p.initial_bearing(&Position::new(PI / 2.0, 0.5)), PI / 2.0);
+        assert_close(p.initial_bearing(&Position::new(PI / 2.0, -0.5)), 3.0 * PI / 2.0);
+
+        let q = Position::new(PI / 4.0, PI / 2.0);
+        assert_close(p.final_bearing(&q), q.initial_bearing(&p) - PI);
+    }
+
+    #[test]
+    fn destination() {
+        let p = Position::new(1.0, 2.0);
+        let q = Position::new(2.0, -0.5);
+        let r = p.destination(p.initial_bearing(&q), p.distance(&q));
+        assert_close(r.distance(&q), 0.0);
+    }
+
+    #[test]
+    fn cross_track_distance() {
+        let start = Position::new(PI / 2.0, 0.0);
+        let end = Position::new(PI / 2.0, 1.0);
+        assert_close(Position::new(PI / 2.0 - 0.1, 0.5).cross_track_distance(&start, &end), -0.1);
+        assert_close(Position::new(PI / 2.0 + 0.2, 0.5).cross_track_distance(&start, &end), 0.2);
+
+        // Degenerate paths.
+        assert_close(Position::new(PI / 2.0 - 0.1, 0.0).cross_track_distance(&start, &start), 0.1);
+        assert_close(Position::new(PI / 2.0 - 0.1, 0.0).cross_track_distance(&start, &Position::new(PI / 2.0, PI)), 0.1);
+    }
+
+    #[test]
+    fn intersection() {
+        let equator = (Position::new(PI / 2.0, 0.0), Position::new(PI / 2.0, 1.0));
+        let meridian = (Position::new(0.5, 0.3), Position::new(1.0, 0.3));
+        let (i, j) = Position::intersection(&equator.0, &equator.1, &meridian.0, &meridian.1).unwrap();
+        assert_close(i.distance(&Position::new(PI / 2.0, 0.3)), 0.0);
+        assert_close(j.distance(&Position::new(PI / 2.0, 0.3 - PI)), 0.0);
+        assert!(Position::intersection(&equator.0, &equator.1, &equator.1, &equator.0).is_none())
+    }
+
+    #[test]
+    fn waypoints() {
+        let p = Position::new(0.3, 0.2);
+        let q = Position::new(2.5, 1.7);
+        let points = p.waypoints(&q, 5);
+        let step = p.distance(&q) / 4.0;
+        for w in points.windows(2) {
+            assert_close(w[0].distance(&w[1]), step)
+        }
+
+        // Degenerate paths.
+        assert!(p.waypoints(&p, 3).iter().all(|w| w.distance(&p).abs() < 1e-9));
+        let antipode = Position::new(PI - 0.3, 0.2 + PI);
+        let points = p.waypoints(&antipode, 3);
+        assert_close(points[1].distance(&p), PI / 2.0);
+        assert_close(points[1].distance(&antipode), PI / 2.0);
+    }
+}
@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::{Arc, Weak};
+use render::Vector3D;
+use render::Context;
+use engine::Transformation;
+
+use crate::Node;
+use crate::location::Vector3d;
+use crate::object::galaxy::{self, Sectors, Coordinates, Parameters, Star};
+
+/// Number of sectors loaded around the focus point, in each direction.
+const RANGE: i32 = 2;
+
+/// Galaxy node.
+///
+/// Positions are in parsecs, relative to the galactic center.
+pub struct Galaxy<C: Context> {
+	/// Parent node.
+	parent: Weak<dyn engine::Node<C>>,
+
+	/// Generated sectors around the focus point.
+	sectors: Sectors,
+
+	/// Sector nodes, instantiated when their sector is loaded.
+	nodes: HashMap<Coordinates, Sector<C>>
+}
+
+impl<C: Context> Galaxy<C> {
+	pub fn new(parent: &Arc<dyn engine::Node<C>>, seed: u64, parameters: Parameters) -> Galaxy<C> {
+		Galaxy {
+			parent: Arc::downgrade(parent),
+			sectors: Sectors::new(crate::object::Galaxy::new(seed, parameters), RANGE),
+			nodes: HashMap::new()
+		}
+	}
+
+	pub fn galaxy(&self) -> &crate::object::Galaxy {
+		self.sectors.galaxy()
+	}
+
+	/// Loaded sector nodes.
+	pub fn sectors(&self) -> impl Iterator<Item = &Sector<C>> {
+		self.nodes.values()
+	}
+}
+
+impl<C: Context> Node for Galaxy<C> {
+	fn focus(&mut self, pos: Vector3D<f32>) {
+		let p = Vector3d::new(pos.x() as f64, pos.y() as f64, pos.z() as f64);
+		self.sectors.focus(p);
+
+		let sectors = &self.sectors;
+		self.nodes.retain(|c, _| sectors.get(*c).is_some());
+		for sector in sectors.sectors() {
+			self.nodes.entry(sector.coordinates).or_insert_with(|| Sector::new(sector.clone()));
+		}
+
+		if let Some(sector) = self.nodes.get_mut(&self.sectors.galaxy().coordinates(p)) {
+			sector.focus(pos)
+		}
+	}
+}
+
+impl<C: Context> engine::Node<C> for Galaxy<C> {
+	fn parent(&self) -> Weak<dyn engine::Node<C>> {
+		self.parent.clone()
+	}
+
+	fn transformation(&self) -> Option<&Transformation> {
+		None
+	}
+
+	fn render(&self, _render: &mut engine::Renderer<C>) {}
+}
+
+/// Sector node, holding the stars of a cubic section of the galaxy.
+pub struct Sector<C: Context> {
+	/// Generated sector.
+	sector: galaxy::Sector,
+
+	/// Star closest to the focus point.
+	nearest: Option<usize>,
+
+	context: PhantomData<C>
+}
+
+impl<C: Context> Sector<C> {
+	pub fn new(sector: galaxy::Sector) -> Sector<C> {
+		Sector {
+			sector,
+			nearest: None,
+			context: PhantomData
+		}
+	}
+
+	pub fn coordinates(&self) -> Coordinates {
+		self.sector.coordinates
+	}
+
+	pub fn stars(&self) -> &[Star] {
+		&self.sector.stars
+	}
+
+	/// Star closest to the focus point, whose system is the next node to generate.
+	pub fn nearest(&self) -> Option<&Star> {
+		self.nearest.map(|i| &self.sector.stars[i])
+	}
+}
+
+impl<C: Context> Node for Sector<C> {
+	fn focus(&mut self, pos: Vector3D<f32>) {
+		let p = Vector3d::new(pos.x() as f64, pos.y() as f64, pos.z() as f64);
+		self.nearest = self.sector.nearest(p);
+	}
+}
@@ -4,11 +4,15 @@
 //! Deeper nodes represent smaller sections but with greater detail.
 //!
 //! Here is the hierachical order of nodes by depth:
+//! - [`Galaxy`]
+//! - [`Sector`]
 //! - [`Planet`]
 
 use render::Vector3D;
 
+pub mod galaxy;
 pub mod planet;
+pub use galaxy::{Galaxy, Sector};
 pub use planet::Planet;
 
 pub trait Node {
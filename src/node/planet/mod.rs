@@ -4,14 +4,13 @@ use render::Context;
 use engine::Transformation;
 
 use crate::{layer, Node};
+use crate::location::Frame;
+use crate::object::Body;
+pub use crate::object::surface::Hit;
 
 mod geometry;
 pub use geometry::Geometry;
-
-pub struct Descriptor {
-	/// Radius of the planet, in m.
-	pub radius: f32
-}
+pub use crate::object::planet::{Descriptor, Model, RayHit};
 
 pub struct Planet<C: Context> {
 	/// Parent node.
@@ -20,6 +19,9 @@ pub struct Planet<C: Context> {
 	/// Global infos about the planet.
 	d: Descriptor,
 
+	/// Generated body, giving the exact surface.
+	body: crate::object::Planet,
+
 	/// Low detail geometry of the planet.
 	geometry: Geometry<C>,
 
@@ -28,13 +30,16 @@ pub struct Planet<C: Context> {
 }
 
 impl<C: Context> Planet<C> {
-	pub fn new(context: &C, parent: &Arc<dyn engine::Node<C>>, d: Descriptor) -> Planet<C> {
+	/// Create the node of a planet, which must already be generated.
+	pub fn new(context: &C, parent: &Arc<dyn engine::Node<C>>, body: crate::object::Planet) -> Planet<C> {
+		let d = *body.descriptor();
 		let geometry = Geometry::new(context, d.radius);
 		let topology = layer::planet::Topology::new(context, &d);
 
 		Planet {
 			parent: Arc::downgrade(parent),
 			d,
+			body,
 			geometry,
 			topology
 		}
@@ -44,14 +49,65 @@ impl<C: Context> Planet<C> {
 		&self.d
 	}
 
+	/// Altitude of the surface point under `pos`, relative to the spheroid.
 	pub fn altitude(&self, pos: Vector3D<f32>) -> f32 {
-		panic!("TODO")
+		self.body.altitude(vec3(pos))
+	}
+
+	/// Checks if the surface point under `pos` is below the sea level.
+	pub fn is_ocean(&self, pos: Vector3D<f32>) -> bool {
+		self.body.is_ocean(vec3(pos))
+	}
+
+	/// Local tangent frame (East-North-Up) at the surface point under `pos` (see [`crate::object::Planet::frame`]).
+	pub fn frame(&self, pos: Vector3D<f32>, terrain: bool) -> Frame {
+		self.body.frame(vec3(pos), terrain)
+	}
+}
+
+/// Physics queries, delegated to the generated body (see [`crate::object::Planet`]).
+///
+/// Positions are relative to the planet center, in the planet frame, in m.
+impl<C: Context> Planet<C> {
+	pub fn raycast(&self, origin: Vector3D<f32>, dir: Vector3D<f32>, model: Model) -> Option<RayHit> {
+		self.body.raycast(vec3(origin), vec3(dir), model)
+	}
+
+	/// Gravitational acceleration at `pos`, including the oblateness term, in m/s².
+	pub fn gravity(&self, pos: Vector3D<f32>) -> Vector3D<f32> {
+		vector(self.body.gravity(vec3(pos)))
 	}
+
+	pub fn closest_point(&self, pos: Vector3D<f32>, model: Model) -> Vector3D<f32> {
+		vector(self.body.closest_point(vec3(pos), model))
+	}
+
+	pub fn signed_distance(&self, pos: Vector3D<f32>, model: Model) -> f32 {
+		self.body.signed_distance(vec3(pos), model)
+	}
+
+	pub fn intersect_segment(&self, a: Vector3D<f32>, b: Vector3D<f32>, model: Model) -> Option<Hit> {
+		self.body.intersect_segment(vec3(a), vec3(b), model)
+	}
+
+	pub fn sphere_cast(&self, a: Vector3D<f32>, b: Vector3D<f32>, radius: f32, model: Model) -> Option<Hit> {
+		self.body.sphere_cast(vec3(a), vec3(b), radius, model)
+	}
+}
+
+fn vec3(v: Vector3D<f32>) -> glam::Vec3 {
+	glam::Vec3::new(v.x(), v.y(), v.z())
+}
+
+fn vector(v: glam::Vec3) -> Vector3D<f32> {
+	Vector3D::new(v.x, v.y, v.z)
 }
 
 impl<C: Context> Node for Planet<C> {
 	fn focus(&mut self, pos: Vector3D<f32>) {
-		let d = self.altitude(pos);
+		// Height above the surface.
+		let pos = vec3(pos);
+		let d = pos.length() - self.body.surface_radius(pos);
 
 		if d > 0.0 {
 			let d_magnitude = (d/1000.0).log(10.0);
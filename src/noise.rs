@@ -0,0 +1,255 @@
+//! Procedural noise.
+//!
+//! Noises are sampled in 3D so that they can be evaluated on the sphere
+//! without seams nor polar distortion.
+
+use glam::Vec3;
+use crate::random::Rng;
+
+/// 3D simplex noise.
+#[derive(Clone)]
+pub struct Simplex {
+	perm: [u8; 512]
+}
+
+/// Gradients of the 3D simplex noise (middles of the cube edges).
+const GRADIENTS: [[f32; 3]; 12] = [
+	[1.0, 1.0, 0.0], [-1.0, 1.0, 0.0], [1.0, -1.0, 0.0], [-1.0, -1.0, 0.0],
+	[1.0, 0.0, 1.0], [-1.0, 0.0, 1.0], [1.0, 0.0, -1.0], [-1.0, 0.0, -1.0],
+	[0.0, 1.0, 1.0], [0.0, -1.0, 1.0], [0.0, 1.0, -1.0], [0.0, -1.0, -1.0]
+];
+
+const F3: f32 = 1.0 / 3.0;
+const G3: f32 = 1.0 / 6.0;
+
+impl Simplex {
+	/// Create a new noise, with a permutation table drawn from the given stream.
+	pub fn new(rng: &mut Rng) -> Simplex {
+		let mut table = [0u8; 256];
+		for (i, p) in table.iter_mut().enumerate() {
+			*p = i as u8
+		}
+
+		for i in (1..256).rev() {
+			let j = rng.below(i as u32 + 1) as usize;
+			table.swap(i, j)
+		}
+
+		let mut perm = [0u8; 512];
+		for i in 0..512 {
+			perm[i] = table[i & 255]
+		}
+
+		Simplex {
+			perm
+		}
+	}
+
+	fn gradient(&self, i: i32, j: i32, k: i32) -> Vec3 {
+		let h = self.perm[(i & 255) as usize + self.perm[(j & 255) as usize + self.perm[(k & 255) as usize] as usize] as usize];
+		let g = GRADIENTS[h as usize % 12];
+		Vec3::new(g[0], g[1], g[2])
+	}
+
+	/// Sample the noise at the given point.
+	///
+	/// The result is in `[-1, 1]`.
+	pub fn sample(&self, p: Vec3) -> f32 {
+		// Skew the input space to find the simplex cell.
+		let s = (p.x + p.y + p.z) * F3;
+		let i = (p.x + s).floor();
+		let j = (p.y + s).floor();
+		let k = (p.z + s).floor();
+		let t = (i + j + k) * G3;
+		let x0 = p - Vec3::new(i - t, j - t, k - t);
+
+		// Find the simplex we are in.
+		let (o1, o2) = if x0.x >= x0.y {
+			if x0.y >= x0.z {
+				([1, 0, 0], [1, 1, 0])
+			} else if x0.x >= x0.z {
+				([1, 0, 0], [1, 0, 1])
+			} else {
+				([0, 0, 1], [1, 0, 1])
+			}
+		} else if x0.y < x0.z {
+			([0, 0, 1], [0, 1, 1])
+		} else if x0.x < x0.z {
+			([0, 1, 0], [0, 1, 1])
+		} else {
+			([0, 1, 0], [1, 1, 0])
+		};
+
+		let (i, j, k) = (i as i32, j as i32, k as i32);
+		let corners = [
+			([0, 0, 0], x0),
+			(o1, x0 - Vec3::new(o1[0] as f32, o1[1] as f32, o1[2] as f32) + Vec3::splat(G3)),
+			(o2, x0 - Vec3::new(o2[0] as f32, o2[1] as f32, o2[2] as f32) + Vec3::splat(2.0 * G3)),
+			([1, 1, 1], x0 - Vec3::splat(1.0 - 3.0 * G3))
+		];
+
+		let mut n = 0.0;
+		for (o, x) in corners.iter() {
+			let t = 0.6 - x.length_squared();
+			if t > 0.0 {
+				let t2 = t * t;
+				n += t2 * t2 * self.gradient(i + o[0], j + o[1], k + o[2]).dot(*x)
+			}
+		}
+
+		32.0 * n
+	}
+}
+
+/// Fractal combination of noise octaves.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Fractal {
+	/// Fractional Brownian motion: sum of octaves.
+	Fbm,
+
+	/// Sum of inverted absolute octaves, giving sharp crests.
+	Ridged,
+
+	/// fBm whose domain is displaced by another fBm.
+	Warped {
+		/// Displacement amplitude, relative to the base frequency wavelength.
+		strength: f32
+	}
+}
+
+/// Fractal noise parameters.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Octaves {
+	/// Number of octaves.
+	pub count: u32,
+
+	/// Frequency of the first octave.
+	pub frequency: f32,
+
+	/// Frequency factor between two octaves.
+	pub lacunarity: f32,
+
+	/// Amplitude factor between two octaves.
+	pub persistence: f32
+}
+
+impl Default for Octaves {
+	fn default() -> Octaves {
+		Octaves {
+			count: 8,
+			frequency: 1.0,
+			lacunarity: 2.0,
+			persistence: 0.5
+		}
+	}
+}
+
+/// Fractal noise.
+#[derive(Clone)]
+pub struct Noise {
+	simplex: Simplex,
+	pub fractal: Fractal,
+	pub octaves: Octaves
+}
+
+/// Domain offsets of the warping noises, to decorrelate them from the base noise.
+const WARP_OFFSETS: [[f32; 3]; 3] = [
+	[31.416, 27.183, 14.142],
+	[-17.321, 22.361, -26.458],
+	[19.937, -11.193, 8.467]
+];
+
+impl Noise {
+	pub fn new(rng: &mut Rng, fractal: Fractal, octaves: Octaves) -> Noise {
+		Noise {
+			simplex: Simplex::new(rng),
+			fractal,
+			octaves
+		}
+	}
+
+	fn fbm(&self, p: Vec3) -> f32 {
+		let mut sum = 0.0;
+		let mut frequency = self.octaves.frequency;
+		let mut amplitude = 1.0;
+		for _ in 0..self.octaves.count {
+			sum += amplitude * self.simplex.sample(p * frequency);
+			frequency *= self.octaves.lacunarity;
+			amplitude *= self.octaves.persistence;
+		}
+
+		sum / self.norm()
+	}
+
+	fn ridged(&self, p: Vec3) -> f32 {
+		let mut sum = 0.0;
+		let mut frequency = self.octaves.frequency;
+		let mut amplitude = 1.0;
+		for _ in 0..self.octaves.count {
+			let r = 1.0 - self.simplex.sample(p * frequency).abs();
+			sum += amplitude * r * r;
+			frequency *= self.octaves.lacunarity;
+			amplitude *= self.octaves.persistence;
+		}
+
+		2.0 * sum / self.norm() - 1.0
+	}
+
+	/// Sum of the octaves amplitudes.
+	fn norm(&self) -> f32 {
+		let mut norm = 0.0;
+		let mut amplitude = 1.0;
+		for _ in 0..self.octaves.count {
+			norm += amplitude;
+			amplitude *= self.octaves.persistence;
+		}
+
+		norm.max(f32::EPSILON)
+	}
+
+	/// Sample the noise at the given point.
+	///
+	/// The result is approximately in `[-1, 1]`.
+	pub fn sample(&self, p: Vec3) -> f32 {
+		match self.fractal {
+			Fractal::Fbm => self.fbm(p),
+			Fractal::Ridged => self.ridged(p),
+			Fractal::Warped { strength } => {
+				let o = WARP_OFFSETS;
+				let w = Vec3::new(
+					self.fbm(p + Vec3::new(o[0][0], o[0][1], o[0][2])),
+					self.fbm(p + Vec3::new(o[1][0], o[1][1], o[1][2])),
+					self.fbm(p + Vec3::new(o[2][0], o[2][1], o[2][2]))
+				);
+
+				self.fbm(p + w * (strength / self.octaves.frequency))
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use glam::Vec3;
+	use crate::random::Rng;
+	use super::{Noise, Fractal, Octaves};
+
+	#[test]
+	fn range() {
+		for fractal in [Fractal::Fbm, Fractal::Ridged, Fractal::Warped { strength: 0.5 }].iter() {
+			let noise = Noise::new(&mut Rng::new(1), *fractal, Octaves::default());
+			let mut rng = Rng::new(2);
+			let mut min = f32::INFINITY;
+			let mut max = f32::NEG_INFINITY;
+			for _ in 0..1000 {
+				let n = noise.sample(rng.unit_vector() * 4.0);
+				min = min.min(n);
+				max = max.max(n);
+			}
+
+			assert!(min >= -1.0 && max <= 1.0);
+			assert!(max - min > 0.5);
+			assert_eq!(noise.sample(Vec3::new(0.1, 0.2, 0.3)), Noise::new(&mut Rng::new(1), *fractal, Octaves::default()).sample(Vec3::new(0.1, 0.2, 0.3)))
+		}
+	}
+}
@@ -0,0 +1,91 @@
+use glam::Vec3;
+use crate::random::Rng;
+use crate::noise::{Noise, Fractal, Octaves};
+use super::body::{Body, Composition};
+
+/// Global parameters of an asteroid.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Descriptor {
+	/// Seed of the shape.
+	pub seed: u64,
+
+	/// Mass, in kg.
+	pub mass: f64,
+
+	/// Longest semi-axis, in m.
+	pub radius: f32,
+
+	/// Other semi-axes, relative to the longest one.
+	pub axes: (f32, f32),
+
+	/// Amplitude of the surface irregularities, relative to the radius.
+	pub roughness: f32,
+
+	pub composition: Composition
+}
+
+impl Default for Descriptor {
+	/// Eros-like asteroid.
+	fn default() -> Descriptor {
+		Descriptor {
+			seed: 0,
+			mass: 6.687e15,
+			radius: 17_000.0,
+			axes: (0.35, 0.33),
+			roughness: 0.1,
+			composition: Composition::rocky()
+		}
+	}
+}
+
+/// Asteroid, an irregular ellipsoid with the longest axis along `x`.
+pub struct Asteroid {
+	d: Descriptor,
+	noise: Noise
+}
+
+impl Asteroid {
+	pub fn new(d: Descriptor) -> Asteroid {
+		let octaves = Octaves {
+			count: 4,
+			frequency: 1.5,
+			..Octaves::default()
+		};
+
+		Asteroid {
+			noise: Noise::new(&mut Rng::new(d.seed).split_str("shape"), Fractal::Fbm, octaves),
+			d
+		}
+	}
+
+	pub fn descriptor(&self) -> &Descriptor {
+		&self.d
+	}
+}
+
+impl Default for Asteroid {
+	fn default() -> Asteroid {
+		Asteroid::new(Descriptor::default())
+	}
+}
+
+impl Body for Asteroid {
+	fn mass(&self) -> f64 {
+		self.d.mass
+	}
+
+	fn composition(&self) -> Composition {
+		self.d.composition
+	}
+
+	fn surface_radius(&self, dir: Vec3) -> f32 {
+		let dir = dir.normalize();
+		let (a, b, c) = (self.d.radius, self.d.radius * self.d.axes.0, self.d.radius * self.d.axes.1);
+		let ellipsoid = 1.0 / (dir.x * dir.x / (a * a) + dir.y * dir.y / (b * b) + dir.z * dir.z / (c * c)).sqrt();
+		ellipsoid * (1.0 + self.d.roughness * self.noise.sample(dir))
+	}
+
+	fn bounding_radius(&self) -> f32 {
+		self.d.radius * (1.0 + self.d.roughness)
+	}
+}
@@ -0,0 +1,119 @@
+use glam::Vec3;
+
+/// Gravitational constant, in m³/kg/s².
+pub const G: f64 = 6.674_30e-11;
+
+/// Bulk composition of a body, as mass fractions.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Composition {
+	/// Iron and nickel.
+	pub metal: f32,
+
+	/// Silicates.
+	pub rock: f32,
+
+	/// Water, ammonia and methane ices.
+	pub ice: f32,
+
+	/// Hydrogen and helium.
+	pub gas: f32
+}
+
+impl Composition {
+	/// Earth-like composition.
+	pub fn rocky() -> Composition {
+		Composition { metal: 0.32, rock: 0.68, ice: 0.0, gas: 0.0 }
+	}
+
+	/// Composition of bodies formed beyond the frost line.
+	pub fn icy() -> Composition {
+		Composition { metal: 0.1, rock: 0.4, ice: 0.5, gas: 0.0 }
+	}
+
+	pub fn metallic() -> Composition {
+		Composition { metal: 0.8, rock: 0.2, ice: 0.0, gas: 0.0 }
+	}
+
+	/// Jupiter-like composition.
+	pub fn gaseous() -> Composition {
+		Composition { metal: 0.01, rock: 0.02, ice: 0.02, gas: 0.95 }
+	}
+
+	/// Typical mean density, in kg/m³ (without compression).
+	pub fn density(&self) -> f32 {
+		let total = self.metal + self.rock + self.ice + self.gas;
+		total / (self.metal / 7_900.0 + self.rock / 3_300.0 + self.ice / 1_000.0 + self.gas / 300.0)
+	}
+}
+
+/// Celestial body.
+///
+/// Positions and directions are relative to the body center, in m.
+pub trait Body {
+	/// Mass, in kg.
+	fn mass(&self) -> f64;
+
+	fn composition(&self) -> Composition;
+
+	/// Emitted power, in W.
+	fn luminosity(&self) -> f64 {
+		0.0
+	}
+
+	/// Distance from the center to the surface in the given direction, in m.
+	fn surface_radius(&self, dir: Vec3) -> f32;
+
+	/// Radius of a sphere containing the whole body, in m.
+	fn bounding_radius(&self) -> f32;
+
+	/// Gravitational acceleration at the given position, in m/s².
+	///
+	/// The body is seen as a point mass by default.
+	fn gravity(&self, p: Vec3) -> Vec3 {
+		let r2 = p.length_squared() as f64;
+		-p.normalize() * (G * self.mass() / r2) as f32
+	}
+
+	/// Magnitude of the gravitational acceleration on the surface in the given direction, in m/s².
+	fn surface_gravity(&self, dir: Vec3) -> f32 {
+		self.gravity(dir.normalize() * self.surface_radius(dir)).length()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use glam::Vec3;
+	use crate::object::{Object, Planet, Star, GasGiant, Asteroid, Rings, Belt};
+	use crate::object::planet::Descriptor;
+	use super::Body;
+
+	#[test]
+	fn bodies() {
+		let earth = Object::Planet(Box::new(Planet::from_seed(0, Descriptor::default())));
+		assert!((earth.surface_gravity(Vec3::unit_x()) - 9.82).abs() < 0.05);
+
+		let sun = Object::Star(Star::default());
+		assert!((sun.surface_gravity(Vec3::unit_x()) - 274.0).abs() < 1.0);
+		assert!(sun.luminosity() > 3.8e26);
+
+		// Gas giants are flattened by their rotation.
+		let jupiter = GasGiant::default();
+		assert!(jupiter.surface_radius(Vec3::unit_z()) < jupiter.surface_radius(Vec3::unit_x()));
+		assert!(jupiter.surface_gravity(Vec3::unit_z()) > jupiter.surface_gravity(Vec3::unit_x()));
+
+		let asteroid = Asteroid::default();
+		for i in 0..100 {
+			let dir = Vec3::new((i as f32).cos(), (i as f32 * 0.7).sin(), (i as f32 * 0.3).cos()).normalize();
+			assert!(asteroid.surface_radius(dir) > 0.0 && asteroid.surface_radius(dir) <= asteroid.bounding_radius())
+		}
+
+		let rings = Object::Rings(Rings::default());
+		assert_eq!(rings.bounding_radius(), Rings::default().descriptor().outer_radius);
+		assert!(rings.surface_gravity(Vec3::unit_x()) > 0.0 && rings.surface_gravity(Vec3::unit_x()).is_finite());
+		assert_eq!(rings.surface_gravity(Vec3::unit_z()), 0.0);
+
+		let belt = Object::Belt(Belt::default());
+		assert!(belt.mass() > 0.0);
+		assert_eq!(belt.surface_gravity(Vec3::unit_x()), 0.0)
+	}
+}
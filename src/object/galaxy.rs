@@ -0,0 +1,338 @@
+use std::collections::HashMap;
+use crate::location::Vector3d;
+use crate::random::Rng;
+use super::star::Class;
+use super::generator::Generator;
+
+/// Parsec, in m.
+pub const PARSEC: f64 = 3.085_677_581e16;
+
+/// Spiral galaxy parameters.
+///
+/// Distances are in parsecs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Parameters {
+	/// Radius of the disk.
+	pub radius: f64,
+
+	/// Scale length of the disk density.
+	pub scale_length: f64,
+
+	/// Scale height of the disk density.
+	pub scale_height: f64,
+
+	/// Radius of the central bulge.
+	pub bulge_radius: f64,
+
+	/// Star density at the center of the disk, in stars/pc³.
+	pub density: f64,
+
+	/// Star density at the center of the bulge, in stars/pc³.
+	pub bulge_density: f64,
+
+	/// Number of spiral arms.
+	pub arms: u32,
+
+	/// Pitch angle of the logarithmic spiral arms, in radians.
+	pub pitch: f64,
+
+	/// Density contrast between the arms and the rest of the disk (between 0 and 1).
+	pub contrast: f64,
+
+	/// Side of a sector.
+	pub sector_size: f64
+}
+
+impl Default for Parameters {
+	/// Milky Way-like galaxy.
+	fn default() -> Parameters {
+		Parameters {
+			radius: 15_000.0,
+			scale_length: 2_600.0,
+			scale_height: 300.0,
+			bulge_radius: 1_000.0,
+			density: 2.5,
+			bulge_density: 10.0,
+			arms: 4,
+			pitch: 0.21,
+			contrast: 0.6,
+			sector_size: 10.0
+		}
+	}
+}
+
+/// Integer coordinates of a sector.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Coordinates {
+	pub x: i32,
+	pub y: i32,
+	pub z: i32
+}
+
+impl Coordinates {
+	pub fn new(x: i32, y: i32, z: i32) -> Coordinates {
+		Coordinates { x, y, z }
+	}
+}
+
+/// Star of a sector.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Star {
+	/// Position in the galaxy, in parsecs.
+	pub position: Vector3d<f64>,
+
+	pub class: Class,
+
+	/// Seed of the star system.
+	pub seed: u64
+}
+
+impl Star {
+	/// Generator of the star system.
+	pub fn system(&self) -> Generator {
+		Generator::new(self.seed, self.class)
+	}
+}
+
+/// Cubic section of a galaxy.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sector {
+	pub coordinates: Coordinates,
+	pub stars: Vec<Star>
+}
+
+impl Sector {
+	/// Index of the star closest to the given position (in parsecs), ignoring invalid positions.
+	pub fn nearest(&self, p: Vector3d<f64>) -> Option<usize> {
+		self.stars.iter().enumerate()
+			.map(|(i, s)| (i, (s.position - p).len()))
+			.filter(|(_, d)| !d.is_nan())
+			.min_by(|(_, a), (_, b)| a.total_cmp(b))
+			.map(|(i, _)| i)
+	}
+}
+
+/// Spiral galaxy, generated sector by sector.
+///
+/// The content of a sector only depends on the galaxy seed and the sector coordinates,
+/// so sectors can be generated in any order.
+#[derive(Clone, Debug)]
+pub struct Galaxy {
+	seed: u64,
+	parameters: Parameters
+}
+
+impl Galaxy {
+	pub fn new(seed: u64, parameters: Parameters) -> Galaxy {
+		Galaxy {
+			seed,
+			parameters
+		}
+	}
+
+	pub fn seed(&self) -> u64 {
+		self.seed
+	}
+
+	pub fn parameters(&self) -> &Parameters {
+		&self.parameters
+	}
+
+	/// Star density at the given position (in parsecs), in stars/pc³.
+	///
+	/// Exponential disk modulated by logarithmic spiral arms, with a gaussian bulge.
+	pub fn density(&self, p: Vector3d<f64>) -> f64 {
+		let g = &self.parameters;
+		let r = (p.x * p.x + p.y * p.y).sqrt();
+		if r > g.radius {
+			return 0.0
+		}
+
+		let theta = p.y.atan2(p.x);
+		let phase = g.arms as f64 * (theta - (r.max(1.0) / g.scale_length).ln() / g.pitch.tan());
+		let arm = ((phase.cos() + 1.0) / 2.0).powi(4);
+		let disk = g.density * (-r / g.scale_length).exp() * (-p.z.abs() / g.scale_height).exp() * (1.0 - g.contrast + 2.0 * g.contrast * arm);
+
+		let s = p.len() / g.bulge_radius;
+		disk + g.bulge_density * (-s * s).exp()
+	}
+
+	/// Coordinates of the sector containing the given position (in parsecs).
+	pub fn coordinates(&self, p: Vector3d<f64>) -> Coordinates {
+		let size = self.parameters.sector_size;
+		Coordinates::new((p.x / size).floor() as i32, (p.y / size).floor() as i32, (p.z / size).floor() as i32)
+	}
+
+	/// Generate the given sector.
+	pub fn sector(&self, c: Coordinates) -> Sector {
+		let size = self.parameters.sector_size;
+		let mut rng = Rng::new(self.seed).split_str("sector").split(c.x as u32 as u64).split(c.y as u32 as u64).split(c.z as u32 as u64);
+		let origin = Vector3d::new(c.x as f64 * size, c.y as f64 * size, c.z as f64 * size);
+		let center = origin + Vector3d::new(size / 2.0, size / 2.0, size / 2.0);
+		let count = poisson(&mut rng, self.density(center) * size * size * size);
+
+		let stars = (0..count).map(|_| {
+			let offset = Vector3d::new(rng.next_f64(), rng.next_f64(), rng.next_f64());
+			Star {
+				position: origin + offset * size,
+				class: class(rng.next_f64()),
+				seed: rng.next_u64()
+			}
+		}).collect();
+
+		Sector {
+			coordinates: c,
+			stars
+		}
+	}
+}
+
+/// Poisson distributed random number of the given mean.
+fn poisson(rng: &mut Rng, mean: f64) -> u32 {
+	if mean < 30.0 {
+		// Knuth's algorithm.
+		let limit = (-mean).exp();
+		let mut count = 0;
+		let mut p = rng.next_f64();
+		while p > limit {
+			count += 1;
+			p *= rng.next_f64()
+		}
+
+		count
+	} else {
+		// Normal approximation (Box-Muller).
+		let (u, v) = (rng.next_f64().max(f64::MIN_POSITIVE), rng.next_f64());
+		let n = (-2.0 * u.ln()).sqrt() * (2.0 * std::f64::consts::PI * v).cos();
+		(mean + mean.sqrt() * n).round().max(0.0) as u32
+	}
+}
+
+/// Spectral class of a random star, with the frequencies of the main sequence stars.
+fn class(x: f64) -> Class {
+	let frequencies = [
+		(Class::M, 0.7645),
+		(Class::K, 0.121),
+		(Class::G, 0.076),
+		(Class::F, 0.03),
+		(Class::A, 0.006),
+		(Class::B, 0.0013)
+	];
+
+	let mut sum = 0.0;
+	for (class, f) in frequencies.iter() {
+		sum += f;
+		if x < sum {
+			return *class
+		}
+	}
+
+	Class::O
+}
+
+/// Sectors around a focus point, generated when the focus gets close and dropped when it goes away.
+pub struct Sectors {
+	galaxy: Galaxy,
+
+	/// Distance (in sectors) up to which sectors are loaded.
+	range: i32,
+
+	loaded: HashMap<Coordinates, Sector>
+}
+
+impl Sectors {
+	pub fn new(galaxy: Galaxy, range: i32) -> Sectors {
+		Sectors {
+			galaxy,
+			range,
+			loaded: HashMap::new()
+		}
+	}
+
+	pub fn galaxy(&self) -> &Galaxy {
+		&self.galaxy
+	}
+
+	/// Move the focus point (in parsecs).
+	///
+	/// Sectors are dropped one sector further than they are loaded, so that they are not regenerated
+	/// when the focus oscillates around a sector border.
+	pub fn focus(&mut self, p: Vector3d<f64>) {
+		let c = self.galaxy.coordinates(p);
+		let r = self.range;
+		let distance = |s: &Coordinates| (s.x - c.x).abs().max((s.y - c.y).abs()).max((s.z - c.z).abs());
+		self.loaded.retain(|s, _| distance(s) <= r + 1);
+
+		for x in -r..=r {
+			for y in -r..=r {
+				for z in -r..=r {
+					let s = Coordinates::new(c.x + x, c.y + y, c.z + z);
+					if !self.loaded.contains_key(&s) {
+						self.loaded.insert(s, self.galaxy.sector(s));
+					}
+				}
+			}
+		}
+	}
+
+	pub fn get(&self, c: Coordinates) -> Option<&Sector> {
+		self.loaded.get(&c)
+	}
+
+	/// Loaded sectors.
+	pub fn sectors(&self) -> impl Iterator<Item = &Sector> {
+		self.loaded.values()
+	}
+
+	/// Loaded stars within the given distance (in parsecs) of the given position.
+	pub fn stars_near(&self, p: Vector3d<f64>, distance: f64) -> Vec<&Star> {
+		self.sectors().flat_map(|s| s.stars.iter()).filter(|s| (s.position - p).len() <= distance).collect()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::location::Vector3d;
+	use crate::object::star::Class;
+	use super::{Galaxy, Parameters, Sectors, Coordinates};
+
+	#[test]
+	fn sectors() {
+		let galaxy = Galaxy::new(3, Parameters::default());
+		let sun = Vector3d::new(8_000.0, 0.0, 20.0);
+		let c = galaxy.coordinates(sun);
+		assert_eq!(galaxy.sector(c), galaxy.sector(c));
+		assert_ne!(galaxy.sector(c).stars, Galaxy::new(4, Parameters::default()).sector(c).stars);
+
+		// Denser toward the center and the galactic plane.
+		assert!(galaxy.density(Vector3d::new(100.0, 0.0, 0.0)) > galaxy.density(sun));
+		assert!(galaxy.density(sun) > galaxy.density(Vector3d::new(8_000.0, 0.0, 1_000.0)));
+		assert_eq!(galaxy.density(Vector3d::new(20_000.0, 0.0, 0.0)), 0.0);
+
+		let stars: Vec<_> = (0..10).flat_map(|x| galaxy.sector(Coordinates::new(c.x + x, c.y, c.z)).stars).collect();
+		assert!(stars.len() > 100);
+		assert!(stars.iter().filter(|s| s.class == Class::M).count() > stars.len() / 2);
+		assert!(stars.iter().all(|s| galaxy.coordinates(s.position).y == c.y));
+
+		let mut sectors = Sectors::new(galaxy.clone(), 1);
+		sectors.focus(sun);
+		assert_eq!(sectors.sectors().count(), 27);
+		let first = sectors.get(c).unwrap().clone();
+
+		// The nearest star is the closest one, whatever the positions.
+		let nearest = first.nearest(sun).unwrap();
+		assert!(first.stars.iter().all(|s| (s.position - sun).len() >= (first.stars[nearest].position - sun).len()));
+		assert_eq!(first.nearest(first.stars[0].position), Some(0));
+		assert_eq!(first.nearest(Vector3d::new(f64::NAN, 0.0, 0.0)), None);
+		assert_eq!(super::Sector { stars: Vec::new(), ..first.clone() }.nearest(sun), None);
+
+		// Moving one sector away keeps the previous ones.
+		sectors.focus(sun + Vector3d::new(10.0, 0.0, 0.0));
+		assert_eq!(sectors.sectors().count(), 36);
+		assert_eq!(sectors.get(c), Some(&first));
+
+		sectors.focus(sun + Vector3d::new(100.0, 0.0, 0.0));
+		assert!(sectors.get(c).is_none());
+		assert!(!sectors.stars_near(sun + Vector3d::new(100.0, 0.0, 0.0), 10.0).is_empty())
+	}
+}
@@ -0,0 +1,325 @@
+use crate::random::Rng;
+use super::{Object, Descriptor, StarSystem, Composition};
+use super::{star, planet, giant, asteroid, rings};
+use super::star::{Class, SOLAR_LUMINOSITY};
+use super::orbit::Elements;
+
+/// Astronomical unit, in m.
+pub const AU: f64 = 1.495_978_707e11;
+
+/// Earth mass, in kg.
+const EARTH_MASS: f64 = 5.972e24;
+
+/// Earth radius, in m.
+const EARTH_RADIUS: f32 = 6_371_000.0;
+
+/// Jupiter mass, in kg.
+const JUPITER_MASS: f64 = 1.898e27;
+
+/// Jupiter radius, in m.
+const JUPITER_RADIUS: f32 = 71_492_000.0;
+
+/// Generated object of a star system.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Entry {
+	/// Descriptor of the object, holding the seed of its own generation if any.
+	pub descriptor: Descriptor,
+
+	/// Index of the parent in the system (the star being 0, and the entry `i` being `i + 1`).
+	pub parent: usize,
+
+	/// Orbit around the parent.
+	pub elements: Elements
+}
+
+/// Generated star system, before the objects are built.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Blueprint {
+	pub seed: u64,
+	pub star: star::Descriptor,
+
+	/// Distance from the star beyond which volatile compounds condense into ices, in m.
+	pub frost_line: f64,
+
+	/// Objects, each one after its parent.
+	pub entries: Vec<Entry>
+}
+
+impl Blueprint {
+	/// Build the objects of the system.
+	pub fn build(&self) -> StarSystem {
+		let mut system = StarSystem::new(Object::Star(star::Star::new(self.star)));
+		for e in &self.entries {
+			system.add(Object::new(e.descriptor), e.parent, e.elements);
+		}
+
+		system
+	}
+}
+
+/// Log-uniform random value in the given range.
+fn log_range(rng: &mut Rng, min: f64, max: f64) -> f64 {
+	(min.ln() + (max.ln() - min.ln()) * rng.next_f64()).exp()
+}
+
+/// Random orientation of a nearly coplanar orbit.
+fn elements(rng: &mut Rng, semi_major_axis: f64, max_eccentricity: f32, max_inclination: f32) -> Elements {
+	let tau = 2.0 * std::f64::consts::PI;
+	Elements {
+		semi_major_axis,
+		eccentricity: (rng.next_f32() * rng.next_f32() * max_eccentricity) as f64,
+		inclination: rng.range(0.0, max_inclination) as f64,
+		ascending_node: rng.next_f64() * tau,
+		periapsis: rng.next_f64() * tau,
+		mean_anomaly: rng.next_f64() * tau,
+		epoch: 0.0
+	}
+}
+
+/// Generator of star systems.
+///
+/// Planets are spaced geometrically (as in the Titius-Bode law), with random ratios ensuring their stability.
+/// Rocky planets form inside the frost line, giants and icy bodies beyond.
+pub struct Generator {
+	seed: u64,
+	class: Class
+}
+
+impl Generator {
+	pub fn new(seed: u64, class: Class) -> Generator {
+		Generator {
+			seed,
+			class
+		}
+	}
+
+	pub fn generate(&self) -> Blueprint {
+		let root = Rng::new(self.seed);
+		let mut rng = root.split_str("system");
+		let (min, max) = self.class.mass_range();
+		let star = star::Descriptor::main_sequence(rng.range(min, max));
+		let luminosity = star.luminosity / SOLAR_LUMINOSITY;
+		let frost_line = 2.7 * AU * luminosity.sqrt();
+
+		let mut blueprint = Blueprint {
+			seed: self.seed,
+			star,
+			frost_line,
+			entries: Vec::new()
+		};
+
+		let inner = 0.1 * AU * luminosity.sqrt().max(0.2);
+		let outer = 40.0 * AU * (star.mass / star::SOLAR_MASS).sqrt();
+		let mut a = inner * rng.range(1.0, 3.0) as f64;
+		let mut belt = false;
+		let mut index = 0;
+		while a < outer {
+			let next = a * rng.range(1.4, 2.0) as f64;
+			let mut body = root.split_str("body").split(index);
+			let seed = body.next_u64();
+			index += 1;
+
+			// Empty orbit.
+			if rng.next_f32() < 0.15 {
+				a = next;
+				continue
+			}
+
+			let giant = a >= frost_line && rng.next_f32() < if next >= frost_line && a < 1.5 * frost_line { 0.8 } else { 0.4 };
+			if !belt && !giant && a < frost_line && next >= frost_line && rng.next_f32() < 0.5 {
+				// Belt left by the perturbations of the first giant.
+				belt = true;
+				blueprint.entries.push(Entry {
+					descriptor: Descriptor::Belt(rings::BeltDescriptor {
+						mass: log_range(&mut body, 1e20, 1e22),
+						inner_radius: (a * 0.85) as f32,
+						outer_radius: (a * 1.15) as f32,
+						..rings::BeltDescriptor::default()
+					}),
+					parent: 0,
+					elements: Elements::circular(a)
+				});
+			} else {
+				self.planet(&mut blueprint, &mut body, seed, a, giant)
+			}
+
+			a = next
+		}
+
+		blueprint
+	}
+
+	/// Add a planet at the given distance, with its moons and rings.
+	fn planet(&self, blueprint: &mut Blueprint, rng: &mut Rng, seed: u64, a: f64, giant: bool) {
+		let star = blueprint.star;
+		let icy = a >= blueprint.frost_line;
+		let (descriptor, mass, radius) = if giant {
+			let mass = log_range(rng, 10.0, 1000.0) * EARTH_MASS;
+			let (radius, composition) = if mass < 0.1 * JUPITER_MASS {
+				// Ice giant.
+				(3.9 * EARTH_RADIUS * ((mass / (15.0 * EARTH_MASS)) as f32).powf(0.3), Composition { metal: 0.05, rock: 0.15, ice: 0.6, gas: 0.2 })
+			} else {
+				(JUPITER_RADIUS * ((mass / JUPITER_MASS) as f32).powf(0.05).min(1.2), Composition::gaseous())
+			};
+
+			(Descriptor::GasGiant(giant::Descriptor {
+				mass,
+				radius,
+				oblateness: rng.range(0.0, 0.1),
+				luminosity: 0.0,
+				composition
+			}), mass, radius)
+		} else {
+			let mass = log_range(rng, 0.03, 5.0) * EARTH_MASS;
+			let radius = EARTH_RADIUS * ((mass / EARTH_MASS) as f32).powf(0.27);
+			(Descriptor::Planet(planet::Descriptor { seed, ..self.terrestrial(rng, &star, a, mass, radius, icy) }), mass, radius)
+		};
+
+		blueprint.entries.push(Entry {
+			descriptor,
+			parent: 0,
+			elements: elements(rng, a, 0.2, 0.05)
+		});
+
+		let parent = blueprint.entries.len();
+
+		// Moons, inside a third of the Hill sphere.
+		let hill = a * (mass / (3.0 * star.mass)).cbrt() / 3.0;
+		let count = if giant { 2 + rng.below(7) } else { rng.below(3) };
+		let mut distance = radius as f64 * rng.range(4.0, 10.0) as f64;
+		for i in 0..count {
+			if distance > hill {
+				break
+			}
+
+			let mut moon = rng.split(i as u64);
+			let seed = moon.next_u64();
+			let moon_mass = mass * log_range(&mut moon, 1e-6, if giant { 1e-4 } else { 1e-2 });
+			let moon_radius = if icy {
+				EARTH_RADIUS * 1.2 * ((moon_mass / EARTH_MASS) as f32).powf(0.3)
+			} else {
+				EARTH_RADIUS * ((moon_mass / EARTH_MASS) as f32).powf(0.27)
+			};
+
+			let descriptor = if moon_radius < 200_000.0 {
+				// Too small to be round.
+				Descriptor::Asteroid(asteroid::Descriptor {
+					seed,
+					mass: moon_mass,
+					radius: moon_radius,
+					axes: (moon.range(0.5, 0.9), moon.range(0.4, 0.8)),
+					roughness: 0.1,
+					composition: if icy { Composition::icy() } else { Composition::rocky() }
+				})
+			} else {
+				Descriptor::Moon(planet::Descriptor { seed, ..self.terrestrial(&mut moon, &star, a, moon_mass, moon_radius, icy) })
+			};
+
+			blueprint.entries.push(Entry {
+				descriptor,
+				parent,
+				elements: elements(&mut moon, distance, 0.05, 0.1)
+			});
+
+			distance *= rng.range(1.4, 2.2) as f64
+		}
+
+		// Rings, inside the Roche limit.
+		if rng.next_f32() < if giant { 0.4 } else { 0.02 } {
+			blueprint.entries.push(Entry {
+				descriptor: Descriptor::Rings(rings::Descriptor {
+					mass: mass * log_range(rng, 1e-10, 1e-7),
+					inner_radius: radius * rng.range(1.1, 1.5),
+					outer_radius: radius * rng.range(1.6, 2.4),
+					composition: if icy { Composition::icy() } else { Composition::rocky() },
+					..rings::Descriptor::default()
+				}),
+				parent,
+				elements: Elements::circular(0.0)
+			})
+		}
+	}
+
+	/// Descriptor of a terrestrial planet or moon (without its seed).
+	fn terrestrial(&self, rng: &mut Rng, star: &star::Descriptor, a: f64, mass: f64, radius: f32, icy: bool) -> planet::Descriptor {
+		// Equilibrium temperature, with some greenhouse effect.
+		let luminosity = star.luminosity / SOLAR_LUMINOSITY;
+		let temperature = 278.6 * luminosity.powf(0.25) / (a / AU).sqrt();
+		let composition = if icy {
+			Composition::icy()
+		} else {
+			// Inner planets are richer in metals.
+			let metal = (0.6 - 0.3 * (a / AU).min(1.0) as f32).max(0.2);
+			Composition { metal, rock: 1.0 - metal, ice: 0.0, gas: 0.0 }
+		};
+
+		let mean_temperature = temperature as f32 - 273.15 + rng.range(0.0, 40.0);
+		let mut d = planet::Descriptor {
+			radius,
+			mass,
+			composition,
+			rotation_period: log_range(rng, 3e4, 1e7) as f32,
+			axial_tilt: rng.range(0.0, 0.5),
+			mean_temperature,
+			..planet::Descriptor::default()
+		};
+
+		// Only bodies heavy enough retain an atmosphere, and oceans need liquid water.
+		d.oblateness = (0.5 * d.angular_velocity().powi(2) * d.radius / d.surface_gravity()).min(0.1);
+		d.atmosphere = if d.escape_velocity() > 4_000.0 {
+			let composition = if icy { planet::Gases::reducing() } else if rng.next_f32() < 0.5 { planet::Gases::carbonic() } else { planet::Gases::earth() };
+			let pressure = log_range(rng, 1e3, 1e7) as f32;
+			planet::Atmosphere::isothermal(pressure, composition, mean_temperature + 273.15, d.surface_gravity())
+		} else {
+			planet::Atmosphere::none()
+		};
+
+		d.ocean_fraction = if !d.atmosphere.is_none() && mean_temperature > 0.0 && mean_temperature < 100.0 { rng.next_f32() } else { 0.0 };
+		d
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::object::{Descriptor, Body};
+	use crate::object::star::Class;
+	use super::Generator;
+
+	#[test]
+	fn systems() {
+		let blueprint = Generator::new(42, Class::G).generate();
+		assert_eq!(blueprint, Generator::new(42, Class::G).generate());
+		assert_ne!(blueprint, Generator::new(43, Class::G).generate());
+		assert_eq!(blueprint.star.class(), Class::G);
+
+		let mut planets = 0;
+		for seed in 0..20 {
+			let blueprint = Generator::new(seed, Class::K).generate();
+			let mut last = 0.0;
+			for (i, e) in blueprint.entries.iter().enumerate() {
+				assert!(e.parent <= i);
+				if e.parent == 0 {
+					// Increasing distances.
+					assert!(e.elements.semi_major_axis > last);
+					last = e.elements.semi_major_axis
+				}
+
+				match e.descriptor {
+					Descriptor::Planet(d) => {
+						planets += 1;
+						assert!(e.elements.semi_major_axis < blueprint.frost_line || d.composition.ice > 0.0)
+					},
+					Descriptor::GasGiant(_) => assert!(e.elements.semi_major_axis >= blueprint.frost_line),
+					_ => ()
+				}
+			}
+
+			let system = blueprint.build();
+			assert_eq!(system.len(), blueprint.entries.len() + 1);
+			assert!((0..system.len()).all(|i| system.position(i, 1e7).len().is_finite()));
+			assert!(system.object(0).luminosity() > 0.0)
+		}
+
+		assert!(planets > 20)
+	}
+}
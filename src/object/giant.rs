@@ -0,0 +1,84 @@
+use glam::Vec3;
+use super::body::{Body, Composition};
+
+/// Global parameters of a gas giant.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Descriptor {
+	/// Mass, in kg.
+	pub mass: f64,
+
+	/// Equatorial radius at the 1 bar level, in m.
+	pub radius: f32,
+
+	/// Flattening of the poles, `1 - polar radius / equatorial radius`.
+	pub oblateness: f32,
+
+	/// Internal heat emitted, in W.
+	pub luminosity: f64,
+
+	pub composition: Composition
+}
+
+impl Default for Descriptor {
+	/// Jupiter-like gas giant.
+	fn default() -> Descriptor {
+		Descriptor {
+			mass: 1.898e27,
+			radius: 71_492_000.0,
+			oblateness: 0.065,
+			luminosity: 3.35e17,
+			composition: Composition::gaseous()
+		}
+	}
+}
+
+/// Gas giant, an oblate spheroid around the `z` axis.
+pub struct GasGiant {
+	d: Descriptor
+}
+
+impl GasGiant {
+	pub fn new(d: Descriptor) -> GasGiant {
+		GasGiant {
+			d
+		}
+	}
+
+	pub fn descriptor(&self) -> &Descriptor {
+		&self.d
+	}
+
+	pub fn polar_radius(&self) -> f32 {
+		self.d.radius * (1.0 - self.d.oblateness)
+	}
+}
+
+impl Default for GasGiant {
+	fn default() -> GasGiant {
+		GasGiant::new(Descriptor::default())
+	}
+}
+
+impl Body for GasGiant {
+	fn mass(&self) -> f64 {
+		self.d.mass
+	}
+
+	fn composition(&self) -> Composition {
+		self.d.composition
+	}
+
+	fn luminosity(&self) -> f64 {
+		self.d.luminosity
+	}
+
+	fn surface_radius(&self, dir: Vec3) -> f32 {
+		let dir = dir.normalize();
+		let (a, c) = (self.d.radius, self.polar_radius());
+		1.0 / ((dir.x * dir.x + dir.y * dir.y) / (a * a) + dir.z * dir.z / (c * c)).sqrt()
+	}
+
+	fn bounding_radius(&self) -> f32 {
+		self.d.radius
+	}
+}
@@ -1,7 +1,108 @@
+pub mod body;
 pub mod planet;
+pub mod star;
+pub mod giant;
+pub mod asteroid;
+pub mod rings;
+pub mod orbit;
+pub mod rotation;
+pub mod surface;
+pub mod system;
+pub mod generator;
+pub mod galaxy;
 
+use glam::Vec3;
+
+pub use body::{Body, Composition};
 pub use planet::Planet;
+pub use star::Star;
+pub use giant::GasGiant;
+pub use asteroid::Asteroid;
+pub use rings::{Rings, Belt};
+pub use orbit::Orbit;
+pub use rotation::{Rotation, Season};
+pub use surface::Surface;
+pub use system::StarSystem;
+pub use generator::Generator;
+pub use galaxy::Galaxy;
 
+/// Celestial body.
 pub enum Object {
-	Planet(Planet)
-}
\ No newline at end of file
+	Star(Star),
+	Planet(Box<Planet>),
+	GasGiant(GasGiant),
+
+	/// Moons are generated like planets.
+	Moon(Box<Planet>),
+	Asteroid(Box<Asteroid>),
+	Rings(Rings),
+	Belt(Belt)
+}
+
+/// Descriptor of a celestial body, from which the object is built.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Descriptor {
+	Star(star::Descriptor),
+	Planet(planet::Descriptor),
+	GasGiant(giant::Descriptor),
+	Moon(planet::Descriptor),
+	Asteroid(asteroid::Descriptor),
+	Rings(rings::Descriptor),
+	Belt(rings::BeltDescriptor)
+}
+
+impl Object {
+	/// Build an object from its descriptor.
+	pub fn new(d: Descriptor) -> Object {
+		match d {
+			Descriptor::Star(d) => Object::Star(Star::new(d)),
+			Descriptor::Planet(d) => Object::Planet(Box::new(Planet::new(d))),
+			Descriptor::GasGiant(d) => Object::GasGiant(GasGiant::new(d)),
+			Descriptor::Moon(d) => Object::Moon(Box::new(Planet::new(d))),
+			Descriptor::Asteroid(d) => Object::Asteroid(Box::new(Asteroid::new(d))),
+			Descriptor::Rings(d) => Object::Rings(Rings::new(d)),
+			Descriptor::Belt(d) => Object::Belt(Belt::new(d))
+		}
+	}
+
+	pub fn body(&self) -> &dyn Body {
+		match self {
+			Object::Star(s) => s,
+			Object::Planet(p) | Object::Moon(p) => p.as_ref(),
+			Object::GasGiant(g) => g,
+			Object::Asteroid(a) => a.as_ref(),
+			Object::Rings(r) => r,
+			Object::Belt(b) => b
+		}
+	}
+}
+
+impl Body for Object {
+	fn mass(&self) -> f64 {
+		self.body().mass()
+	}
+
+	fn composition(&self) -> Composition {
+		self.body().composition()
+	}
+
+	fn luminosity(&self) -> f64 {
+		self.body().luminosity()
+	}
+
+	fn surface_radius(&self, dir: Vec3) -> f32 {
+		self.body().surface_radius(dir)
+	}
+
+	fn bounding_radius(&self) -> f32 {
+		self.body().bounding_radius()
+	}
+
+	fn gravity(&self, p: Vec3) -> Vec3 {
+		self.body().gravity(p)
+	}
+
+	fn surface_gravity(&self, dir: Vec3) -> f32 {
+		self.body().surface_gravity(dir)
+	}
+}
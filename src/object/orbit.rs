@@ -0,0 +1,200 @@
+use crate::location::Vector3d;
+use super::body::G;
+
+/// Keplerian elements of an elliptic orbit.
+///
+/// Angles are in radians, relative to the `xy` reference plane of the parent body,
+/// the `x` axis pointing toward the reference direction.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Elements {
+	/// Semi-major axis, in m.
+	pub semi_major_axis: f64,
+
+	/// Eccentricity, between 0 (circle) and 1 (excluded).
+	pub eccentricity: f64,
+
+	/// Inclination of the orbital plane.
+	pub inclination: f64,
+
+	/// Longitude of the ascending node.
+	pub ascending_node: f64,
+
+	/// Argument of the periapsis.
+	pub periapsis: f64,
+
+	/// Mean anomaly at the epoch.
+	pub mean_anomaly: f64,
+
+	/// Reference time, in s.
+	pub epoch: f64
+}
+
+impl Elements {
+	/// Circular orbit in the reference plane.
+	pub fn circular(radius: f64) -> Elements {
+		Elements {
+			semi_major_axis: radius,
+			eccentricity: 0.0,
+			inclination: 0.0,
+			ascending_node: 0.0,
+			periapsis: 0.0,
+			mean_anomaly: 0.0,
+			epoch: 0.0
+		}
+	}
+}
+
+/// Position and velocity relative to the parent body, in m and m/s.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct State {
+	pub position: Vector3d<f64>,
+	pub velocity: Vector3d<f64>
+}
+
+/// Keplerian orbit around a parent body.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Orbit {
+	elements: Elements,
+
+	/// Standard gravitational parameter of the two bodies, in m³/s².
+	mu: f64
+}
+
+impl Orbit {
+	/// Orbit of a body of mass `mass` around a parent of mass `parent_mass` (in kg).
+	pub fn new(elements: Elements, parent_mass: f64, mass: f64) -> Orbit {
+		debug_assert!(elements.eccentricity >= 0.0 && elements.eccentricity < 1.0);
+		Orbit {
+			elements,
+			mu: G * (parent_mass + mass)
+		}
+	}
+
+	pub fn elements(&self) -> &Elements {
+		&self.elements
+	}
+
+	/// Standard gravitational parameter of the two bodies, in m³/s².
+	pub fn mu(&self) -> f64 {
+		self.mu
+	}
+
+	/// Mean motion, in rad/s.
+	pub fn mean_motion(&self) -> f64 {
+		(self.mu / self.elements.semi_major_axis.powi(3)).sqrt()
+	}
+
+	/// Orbital period, in s.
+	pub fn period(&self) -> f64 {
+		2.0 * std::f64::consts::PI / self.mean_motion()
+	}
+
+	/// Normal of the orbital plane (unit vector), along the angular momentum.
+	pub fn normal(&self) -> Vector3d<f64> {
+		let (so, co) = self.elements.ascending_node.sin_cos();
+		let (si, ci) = self.elements.inclination.sin_cos();
+		Vector3d::new(so * si, -co * si, ci)
+	}
+
+	/// Direction of the ascending node (unit vector), in the reference plane.
+	pub fn node(&self) -> Vector3d<f64> {
+		let (so, co) = self.elements.ascending_node.sin_cos();
+		Vector3d::new(co, so, 0.0)
+	}
+
+	/// Eccentric anomaly at the given time, solving Kepler's equation.
+	pub fn eccentric_anomaly(&self, t: f64) -> f64 {
+		let e = self.elements.eccentricity;
+		let m = (self.elements.mean_anomaly + self.mean_motion() * (t - self.elements.epoch)).rem_euclid(2.0 * std::f64::consts::PI);
+
+		// Newton's method.
+		let mut anomaly = if e < 0.8 { m } else { std::f64::consts::PI };
+		for _ in 0..50 {
+			let delta = (anomaly - e * anomaly.sin() - m) / (1.0 - e * anomaly.cos());
+			anomaly -= delta;
+			if delta.abs() < 1e-14 {
+				break
+			}
+		}
+
+		anomaly
+	}
+
+	/// Position and velocity relative to the parent at the given time (in s).
+	///
+	/// An orbit with a null semi-major axis stays at the parent center (e.g. rings).
+	pub fn state(&self, t: f64) -> State {
+		let el = &self.elements;
+		if el.semi_major_axis == 0.0 {
+			return State {
+				position: Vector3d::new(0.0, 0.0, 0.0),
+				velocity: Vector3d::new(0.0, 0.0, 0.0)
+			}
+		}
+
+		let (a, e) = (el.semi_major_axis, el.eccentricity);
+		let anomaly = self.eccentric_anomaly(t);
+		let (sin, cos) = anomaly.sin_cos();
+		let b = (1.0 - e * e).sqrt();
+
+		// In the orbital plane, the x axis pointing toward the periapsis.
+		let speed = self.mean_motion() * a / (1.0 - e * cos);
+		let p = (a * (cos - e), a * b * sin);
+		let v = (-speed * sin, speed * b * cos);
+
+		let rotate = |(x, y): (f64, f64)| {
+			let (so, co) = el.ascending_node.sin_cos();
+			let (si, ci) = el.inclination.sin_cos();
+			let (sw, cw) = el.periapsis.sin_cos();
+			let (x, y) = (cw * x - sw * y, sw * x + cw * y);
+			let (y, z) = (ci * y, si * y);
+			Vector3d::new(co * x - so * y, so * x + co * y, z)
+		};
+
+		State {
+			position: rotate(p),
+			velocity: rotate(v)
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{Orbit, Elements};
+
+	#[test]
+	fn kepler() {
+		let earth = Orbit::new(Elements::circular(1.495_978_707e11), 1.989e30, 5.972e24);
+		assert!((earth.period() / 86_400.0 - 365.25).abs() < 0.1);
+
+		let elements = Elements {
+			semi_major_axis: 3.84e8,
+			eccentricity: 0.5,
+			inclination: 0.3,
+			ascending_node: 1.0,
+			periapsis: 2.0,
+			mean_anomaly: 0.5,
+			epoch: 1000.0
+		};
+
+		// Vis-viva equation and conservation of the angular momentum.
+		let orbit = Orbit::new(elements, 5.972e24, 7.342e22);
+		let momentum = |t: f64| {
+			let s = orbit.state(t);
+			s.position * s.velocity
+		};
+
+		for i in 0..20 {
+			let t = i as f64 * 1e5;
+			let s = orbit.state(t);
+			let (r, v) = (s.position.len(), s.velocity.len());
+			assert!(((v * v) / (orbit.mu() * (2.0 / r - 1.0 / elements.semi_major_axis)) - 1.0).abs() < 1e-9);
+			assert!((momentum(t) - momentum(0.0)).len() / momentum(0.0).len() < 1e-9);
+			assert!((momentum(t).normalize() - orbit.normal()).len() < 1e-9)
+		}
+
+		// Periodicity.
+		let (a, b) = (orbit.state(123.0).position, orbit.state(123.0 + orbit.period()).position);
+		assert!((a - b).len() < 1e-3)
+	}
+}
@@ -0,0 +1,101 @@
+/// Ideal gas constant, in J/mol/K.
+pub const R: f32 = 8.314_462;
+
+/// Composition of an atmosphere, as volume fractions.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Gases {
+	pub nitrogen: f32,
+	pub oxygen: f32,
+	pub argon: f32,
+	pub carbon_dioxide: f32,
+	pub methane: f32,
+	pub hydrogen: f32,
+	pub helium: f32
+}
+
+impl Gases {
+	/// Earth-like composition.
+	pub fn earth() -> Gases {
+		Gases { nitrogen: 0.781, oxygen: 0.209, argon: 0.0093, carbon_dioxide: 0.0004, methane: 0.0, hydrogen: 0.0, helium: 0.0 }
+	}
+
+	/// Venus or Mars-like composition.
+	pub fn carbonic() -> Gases {
+		Gases { nitrogen: 0.03, oxygen: 0.0, argon: 0.0, carbon_dioxide: 0.965, methane: 0.0, hydrogen: 0.0, helium: 0.0 }
+	}
+
+	/// Titan-like composition.
+	pub fn reducing() -> Gases {
+		Gases { nitrogen: 0.95, oxygen: 0.0, argon: 0.0, carbon_dioxide: 0.0, methane: 0.049, hydrogen: 0.001, helium: 0.0 }
+	}
+
+	/// Mean molar mass, in kg/mol.
+	pub fn molar_mass(&self) -> f32 {
+		let sum = self.nitrogen + self.oxygen + self.argon + self.carbon_dioxide + self.methane + self.hydrogen + self.helium;
+		let mass = self.nitrogen * 0.028_014 + self.oxygen * 0.031_998 + self.argon * 0.039_948
+			+ self.carbon_dioxide * 0.044_009 + self.methane * 0.016_043 + self.hydrogen * 0.002_016 + self.helium * 0.004_003;
+
+		if sum > 0.0 { mass / sum } else { 0.0 }
+	}
+}
+
+/// Atmosphere of a planet, with an exponential pressure profile.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Atmosphere {
+	/// Pressure at sea level, in Pa.
+	pub pressure: f32,
+
+	pub composition: Gases,
+
+	/// Altitude over which the pressure decreases by a factor `e`, in m.
+	pub scale_height: f32
+}
+
+impl Atmosphere {
+	/// No atmosphere.
+	pub fn none() -> Atmosphere {
+		Atmosphere {
+			pressure: 0.0,
+			composition: Gases::earth(),
+			scale_height: 0.0
+		}
+	}
+
+	/// Isothermal atmosphere, whose scale height follows the temperature (in K) and the gravity (in m/s²).
+	pub fn isothermal(pressure: f32, composition: Gases, temperature: f32, gravity: f32) -> Atmosphere {
+		Atmosphere {
+			pressure,
+			composition,
+			scale_height: R * temperature / (composition.molar_mass() * gravity)
+		}
+	}
+
+	pub fn is_none(&self) -> bool {
+		self.pressure <= 0.0
+	}
+
+	/// Pressure at the given altitude above the sea level, in Pa.
+	pub fn pressure_at(&self, altitude: f32) -> f32 {
+		if self.is_none() {
+			0.0
+		} else {
+			self.pressure * (-altitude / self.scale_height).exp()
+		}
+	}
+
+	/// Density at the given altitude and temperature (in K), in kg/m³.
+	pub fn density_at(&self, altitude: f32, temperature: f32) -> f32 {
+		self.pressure_at(altitude) * self.composition.molar_mass() / (R * temperature)
+	}
+}
+
+impl Default for Atmosphere {
+	/// Earth-like atmosphere.
+	fn default() -> Atmosphere {
+		Atmosphere {
+			pressure: 101_325.0,
+			composition: Gases::earth(),
+			scale_height: 8_500.0
+		}
+	}
+}
@@ -0,0 +1,310 @@
+use std::fmt;
+use glam::Vec3;
+use crate::topology::{Cell, Storage, Element};
+use super::{Layer, Context, Field, Elevation, Climate};
+
+/// Biome definition.
+///
+/// A biome covers the points whose climate and altitude fall in its ranges.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Biome {
+	pub name: String,
+
+	/// Mean annual temperature range, in °C.
+	pub temperature: (f32, f32),
+
+	/// Annual precipitation range, in mm.
+	pub precipitation: (f32, f32),
+
+	/// Altitude range (relative to the sea level), in m.
+	pub altitude: (f32, f32),
+
+	/// Forest density, between 0 and 1.
+	pub forest: f32
+}
+
+impl Biome {
+	pub fn new(name: &str, temperature: (f32, f32), precipitation: (f32, f32), altitude: (f32, f32), forest: f32) -> Biome {
+		Biome {
+			name: name.to_string(),
+			temperature,
+			precipitation,
+			altitude,
+			forest
+		}
+	}
+}
+
+/// Biome table parsing error.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+	/// Line number (starting from 1).
+	pub line: usize,
+
+	pub message: String
+}
+
+impl fmt::Display for ParseError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "line {}: {}", self.line, self.message)
+	}
+}
+
+impl std::error::Error for ParseError {}
+
+const INFINITY: f32 = f32::INFINITY;
+
+/// Biome table.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct Table {
+	biomes: Vec<Biome>
+}
+
+impl Table {
+	pub fn new() -> Table {
+		Table {
+			biomes: Vec::new()
+		}
+	}
+
+	/// Whittaker-like table of Earth biomes.
+	pub fn whittaker() -> Table {
+		let land = (0.0, 3000.0);
+		let mut table = Table::new();
+		table.add(Biome::new("ocean", (-INFINITY, INFINITY), (-INFINITY, INFINITY), (-INFINITY, 0.0), 0.0));
+		table.add(Biome::new("ice", (-INFINITY, -15.0), (-INFINITY, INFINITY), (0.0, INFINITY), 0.0));
+		table.add(Biome::new("tundra", (-15.0, -5.0), (-INFINITY, INFINITY), land, 0.05));
+		table.add(Biome::new("cold desert", (-5.0, 5.0), (-INFINITY, 300.0), land, 0.05));
+		table.add(Biome::new("taiga", (-5.0, 5.0), (300.0, INFINITY), land, 0.7));
+		table.add(Biome::new("desert", (5.0, INFINITY), (-INFINITY, 250.0), land, 0.0));
+		table.add(Biome::new("temperate grassland", (5.0, 20.0), (250.0, 750.0), land, 0.1));
+		table.add(Biome::new("temperate forest", (5.0, 20.0), (750.0, 2000.0), land, 0.8));
+		table.add(Biome::new("temperate rainforest", (5.0, 20.0), (2000.0, INFINITY), land, 1.0));
+		table.add(Biome::new("savanna", (20.0, INFINITY), (250.0, 1500.0), land, 0.25));
+		table.add(Biome::new("tropical rainforest", (20.0, INFINITY), (1500.0, INFINITY), land, 1.0));
+		table.add(Biome::new("alpine", (-15.0, INFINITY), (-INFINITY, INFINITY), (3000.0, INFINITY), 0.0));
+		table
+	}
+
+	/// Parse a table, with one biome per line:
+	///
+	/// ```text
+	/// # name   temperature    precipitation   altitude      forest
+	/// savanna  20 inf         250 1500        0 3000        0.25
+	/// ```
+	///
+	/// Names use `_` instead of spaces. Empty lines and `#` comments are ignored.
+	pub fn parse(text: &str) -> Result<Table, ParseError> {
+		let mut table = Table::new();
+		for (i, line) in text.lines().enumerate() {
+			let line = line.split('#').next().unwrap().trim();
+			if line.is_empty() {
+				continue
+			}
+
+			let error = |message: String| ParseError { line: i + 1, message };
+			let fields: Vec<&str> = line.split_whitespace().collect();
+			if fields.len() != 8 {
+				return Err(error(format!("expected 8 fields, found {}", fields.len())))
+			}
+
+			let mut values = [0.0f32; 7];
+			for (v, f) in values.iter_mut().zip(&fields[1..]) {
+				*v = f.parse().map_err(|_| error(format!("invalid number `{}`", f)))?
+			}
+
+			table.add(Biome::new(&fields[0].replace('_', " "), (values[0], values[1]), (values[2], values[3]), (values[4], values[5]), values[6]))
+		}
+
+		Ok(table)
+	}
+
+	pub fn add(&mut self, biome: Biome) {
+		self.biomes.push(biome)
+	}
+
+	pub fn biomes(&self) -> &[Biome] {
+		&self.biomes
+	}
+
+	pub fn get(&self, index: usize) -> &Biome {
+		&self.biomes[index]
+	}
+
+	pub fn find(&self, name: &str) -> Option<usize> {
+		self.biomes.iter().position(|b| b.name == name)
+	}
+}
+
+/// Blend margins at the biome borders.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Margins {
+	/// Temperature margin, in °C.
+	pub temperature: f32,
+
+	/// Precipitation margin, in mm.
+	pub precipitation: f32,
+
+	/// Altitude margin, in m.
+	pub altitude: f32
+}
+
+/// Biomes layer parameters.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Parameters {
+	/// Depth of the cells where the biomes are computed.
+	pub depth: u32,
+
+	pub margins: Margins
+}
+
+impl Default for Parameters {
+	fn default() -> Parameters {
+		Parameters {
+			depth: 5,
+			margins: Margins {
+				temperature: 2.0,
+				precipitation: 100.0,
+				altitude: 100.0
+			}
+		}
+	}
+}
+
+/// Smooth membership of `x` in the range `[min, max]`, blended over `margin` on each side of the bounds.
+fn membership(x: f32, (min, max): (f32, f32), margin: f32) -> f32 {
+	let step = |t: f32| {
+		let t = t.clamp(0.0, 1.0);
+		t * t * (3.0 - 2.0 * t)
+	};
+
+	let margin = margin.max(f32::EPSILON);
+	let low = if min == -INFINITY { 1.0 } else { step((x - min + margin) / (2.0 * margin)) };
+	let high = if max == INFINITY { 1.0 } else { step((max + margin - x) / (2.0 * margin)) };
+	low * high
+}
+
+/// Biomes layer.
+///
+/// Maps the climate and altitude of each cell to a blend of biomes of a table.
+pub struct Biomes {
+	parameters: Parameters,
+	table: Table,
+
+	/// Biome weights of each cell (summing to 1).
+	weights: Storage<Vec<(u16, f32)>>
+}
+
+impl Biomes {
+	pub const NAME: &'static str = "biomes";
+
+	pub fn new(parameters: Parameters, table: Table) -> Biomes {
+		Biomes {
+			parameters,
+			table,
+			weights: Storage::sparse(Element::Face, parameters.depth)
+		}
+	}
+
+	pub fn parameters(&self) -> &Parameters {
+		&self.parameters
+	}
+
+	pub fn table(&self) -> &Table {
+		&self.table
+	}
+
+	/// Biome weights (summing to 1) for the given climate and altitude.
+	pub fn classify(&self, temperature: f32, precipitation: f32, altitude: f32) -> Vec<(u16, f32)> {
+		let m = &self.parameters.margins;
+		let mut weights: Vec<(u16, f32)> = self.table.biomes.iter().enumerate().filter_map(|(i, b)| {
+			let w = membership(temperature, b.temperature, m.temperature)
+				* membership(precipitation, b.precipitation, m.precipitation)
+				* membership(altitude, b.altitude, m.altitude);
+			if w > 0.0 { Some((i as u16, w)) } else { None }
+		}).collect();
+
+		let total: f32 = weights.iter().map(|(_, w)| w).sum();
+		if total > 0.0 {
+			for (_, w) in &mut weights {
+				*w /= total
+			}
+		}
+
+		weights
+	}
+
+	/// Biome weights (summing to 1) in the given cell.
+	///
+	/// It is empty if no biome of the table matches.
+	pub fn weights(&self, cell: Cell) -> &[(u16, f32)] {
+		self.weights.face(cell).unwrap()
+	}
+
+	/// Dominant biome in the given cell.
+	pub fn biome(&self, cell: Cell) -> Option<&Biome> {
+		self.weights(cell).iter()
+			.fold(None, |best: Option<(u16, f32)>, (i, w)| if best.map(|b| *w > b.1).unwrap_or(true) { Some((*i, *w)) } else { best })
+			.map(|(i, _)| self.table.get(i as usize))
+	}
+
+	/// Forest density (between 0 and 1) in the given direction.
+	pub fn forest(&self, dir: Vec3) -> f32 {
+		self.weights(Cell::locate(dir, self.parameters.depth)).iter().map(|(i, w)| w * self.table.get(*i as usize).forest).sum()
+	}
+}
+
+impl Default for Biomes {
+	fn default() -> Biomes {
+		Biomes::new(Parameters::default(), Table::whittaker())
+	}
+}
+
+impl Layer for Biomes {
+	fn name(&self) -> &str {
+		Biomes::NAME
+	}
+
+	fn inputs(&self) -> Vec<String> {
+		vec![Elevation::NAME.to_string(), Climate::NAME.to_string()]
+	}
+
+	fn evaluate(&mut self, context: &mut Context) {
+		let p = self.parameters;
+		let sea_level = context.descriptor.sea_level;
+		let elevation = context.layer::<Elevation>(Elevation::NAME).unwrap();
+		let climate = context.layer::<Climate>(Climate::NAME).unwrap();
+		self.weights = Storage::from_values(Element::Face, p.depth, Cell::all(p.depth).map(|cell| {
+			let c = climate.sample_cell(cell);
+			self.classify(c.temperature, c.precipitation, elevation.sample_cell(cell) - sea_level)
+		}).collect())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{Biomes, Table, Parameters};
+
+	#[test]
+	fn table() {
+		let biomes = Biomes::default();
+		let name = |w: Vec<(u16, f32)>| biomes.table().get(w.iter().max_by(|a, b| a.1.partial_cmp(&b.1).unwrap()).unwrap().0 as usize).name.clone();
+		assert_eq!(name(biomes.classify(25.0, 3000.0, 100.0)), "tropical rainforest");
+		assert_eq!(name(biomes.classify(0.0, 600.0, 100.0)), "taiga");
+		assert_eq!(name(biomes.classify(25.0, 50.0, 100.0)), "desert");
+		assert_eq!(name(biomes.classify(10.0, 1000.0, -500.0)), "ocean");
+
+		// Smooth border.
+		let border = biomes.classify(20.0, 3000.0, 100.0);
+		assert_eq!(border.len(), 2);
+		assert!(border.iter().all(|(_, w)| (*w - 0.5).abs() < 1e-3));
+
+		let alien = Table::parse("# alien biomes\ncrystal_forest -50 -20 0 inf 0 inf 0.6\n").unwrap();
+		assert_eq!(alien.get(0).name, "crystal forest");
+		assert!(Table::parse("bad 1 2").is_err());
+
+		let biomes = Biomes::new(Parameters::default(), alien);
+		assert!((biomes.classify(-30.0, 100.0, 10.0)[0].1 - 1.0).abs() < 1e-6);
+		assert!(biomes.classify(0.0, 100.0, 10.0).is_empty())
+	}
+}
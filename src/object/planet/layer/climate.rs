@@ -0,0 +1,263 @@
+use glam::Vec3;
+use crate::location::Frame;
+use crate::topology::{Cell, Storage, Element};
+use super::{Layer, Context, Field, Elevation};
+
+/// Climate layer parameters.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Parameters {
+	/// Depth of the cells where the climate is computed.
+	pub depth: u32,
+
+	/// Temperature difference (in °C) per unit of relative insolation.
+	pub contrast: f32,
+
+	/// Angular distance from the sea (upwind) at which the moisture is divided by `e`, in radians.
+	pub moisture_decay: f32,
+
+	/// Height of upwind relief dividing the precipitation by `e` (rain shadow), in m.
+	pub shadow_height: f32,
+
+	/// Upwind slope height doubling the precipitation (orographic rain), in m.
+	pub orographic_height: f32,
+
+	/// Angular step of the upwind march, in radians.
+	pub step: f32,
+
+	/// Number of steps of the upwind march.
+	pub steps: u32
+}
+
+impl Default for Parameters {
+	fn default() -> Parameters {
+		Parameters {
+			depth: 5,
+			contrast: 72.0,
+			moisture_decay: 0.15,
+			shadow_height: 1500.0,
+			orographic_height: 500.0,
+			step: 0.02,
+			steps: 20
+		}
+	}
+}
+
+/// Climate at a given point.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sample {
+	/// Mean annual temperature, in °C.
+	pub temperature: f32,
+
+	/// Annual precipitation, in mm.
+	pub precipitation: f32
+}
+
+/// Latitude of the given direction, the rotation axis being `z`.
+fn latitude(dir: Vec3) -> f32 {
+	libm::asinf(dir.normalize().z.clamp(-1.0, 1.0))
+}
+
+/// Mean annual insolation relative to the planet mean,
+/// with the second order Legendre approximation of its distribution.
+pub fn insolation(latitude: f32, axial_tilt: f32) -> f32 {
+	let c = libm::cosf(axial_tilt);
+	let s2 = -5.0 / 16.0 * (3.0 * c * c - 1.0);
+	let x = libm::sinf(latitude);
+	1.0 + s2 * (3.0 * x * x - 1.0) / 2.0
+}
+
+/// Daily mean insolation relative to the planet annual mean, for the given declination of the star
+/// (see [`Rotation::declination`](crate::object::Rotation::declination)).
+///
+/// Gives the seasonal variation of the heating.
+pub fn daily_insolation(latitude: f32, declination: f32) -> f32 {
+	let cos = (-libm::tanf(latitude) * libm::tanf(declination)).clamp(-1.0, 1.0);
+	let h = libm::acosf(cos);
+	4.0 / std::f32::consts::PI * (h * libm::sinf(latitude) * libm::sinf(declination) + libm::cosf(latitude) * libm::cosf(declination) * libm::sinf(h))
+}
+
+/// Prevailing surface wind (unit tangent vector) in the given direction.
+///
+/// Trade winds in the Hadley cells (up to 30°), westerlies in the Ferrel cells (up to 60°)
+/// and polar easterlies beyond.
+pub fn wind(dir: Vec3) -> Vec3 {
+	let frame = Frame::new(dir);
+	let phi = latitude(dir);
+	let poleward = frame.north * phi.signum();
+	let w = match phi.abs().to_degrees() {
+		l if l < 30.0 => -frame.east - poleward * 0.3,
+		l if l < 60.0 => frame.east + poleward * 0.3,
+		_ => -frame.east - poleward * 0.3
+	};
+
+	w.normalize()
+}
+
+/// Climate layer.
+///
+/// Temperature depends on the latitude (through the axial tilt) and the altitude.
+/// Precipitation follows the atmospheric circulation bands, modulated by the distance to the sea upwind,
+/// rain shadows and orographic lift.
+pub struct Climate {
+	parameters: Parameters,
+	temperature: Storage<f32>,
+	precipitation: Storage<f32>
+}
+
+impl Climate {
+	pub const NAME: &'static str = "climate";
+
+	pub fn new(parameters: Parameters) -> Climate {
+		Climate {
+			parameters,
+			temperature: Storage::sparse(Element::Face, parameters.depth),
+			precipitation: Storage::sparse(Element::Face, parameters.depth)
+		}
+	}
+
+	pub fn parameters(&self) -> &Parameters {
+		&self.parameters
+	}
+
+	/// Mean annual temperature in the given cell, in °C.
+	pub fn temperature(&self, cell: Cell) -> f32 {
+		*self.temperature.face(cell).unwrap()
+	}
+
+	/// Annual precipitation in the given cell, in mm.
+	pub fn precipitation(&self, cell: Cell) -> f32 {
+		*self.precipitation.face(cell).unwrap()
+	}
+}
+
+impl Default for Climate {
+	fn default() -> Climate {
+		Climate::new(Parameters::default())
+	}
+}
+
+impl Field<Sample> for Climate {
+	fn sample(&self, dir: Vec3) -> Sample {
+		self.sample_cell(Cell::locate(dir, self.parameters.depth))
+	}
+
+	fn sample_cell(&self, cell: Cell) -> Sample {
+		Sample {
+			temperature: self.temperature(cell),
+			precipitation: self.precipitation(cell)
+		}
+	}
+}
+
+impl Layer for Climate {
+	fn name(&self) -> &str {
+		Climate::NAME
+	}
+
+	fn inputs(&self) -> Vec<String> {
+		vec![Elevation::NAME.to_string()]
+	}
+
+	fn evaluate(&mut self, context: &mut Context) {
+		let p = self.parameters;
+		let d = context.descriptor;
+		let sea_level = d.sea_level;
+		let elevation = context.layer::<Elevation>(Elevation::NAME).unwrap();
+		let altitudes: Vec<f32> = Cell::all(p.depth).map(|c| elevation.sample_cell(c) - sea_level).collect();
+		let altitude = |dir: Vec3| altitudes[Cell::locate(dir, p.depth).index() as usize];
+
+		self.temperature = Storage::from_values(Element::Face, p.depth, Cell::all(p.depth).map(|cell| {
+			let dir = cell.center();
+			let t = d.mean_temperature + p.contrast * (insolation(latitude(dir), d.axial_tilt) - 1.0);
+			t - d.lapse_rate * altitudes[cell.index() as usize].max(0.0)
+		}).collect());
+
+		let mut precipitation: Vec<f32> = Cell::all(p.depth).map(|cell| {
+			let dir = cell.center();
+			let phi = latitude(dir);
+			let s = libm::sinf(phi);
+			let band = (1.0 + 0.8 * libm::cosf(6.0 * phi)) * (1.0 - 0.5 * s * s);
+
+			let own = altitudes[cell.index() as usize];
+			if own < 0.0 {
+				return band
+			}
+
+			// March upwind, up to the sea.
+			let mut q = dir;
+			let mut distance = p.step * p.steps as f32;
+			let mut relief = own;
+			let mut upwind = None;
+			for i in 1..=p.steps {
+				q = (q - wind(q) * p.step).normalize();
+				let a = altitude(q);
+				if upwind.is_none() {
+					upwind = Some(a)
+				}
+
+				if a < 0.0 {
+					distance = p.step * i as f32;
+					break
+				}
+
+				relief = relief.max(a)
+			}
+
+			let moisture = libm::expf(-distance / p.moisture_decay);
+			let shadow = libm::expf(-(relief - own) / p.shadow_height);
+			let lift = ((own - upwind.unwrap_or(own).max(0.0)) / p.orographic_height).clamp(0.0, 2.0);
+			band * moisture * shadow * (1.0 + lift)
+		}).collect();
+
+		// Scale to the planet mean precipitation.
+		let total: f32 = Cell::all(p.depth).map(|c| c.area() * precipitation[c.index() as usize]).sum();
+		let scale = d.precipitation * 4.0 * std::f32::consts::PI / total.max(f32::EPSILON);
+		for v in &mut precipitation {
+			*v *= scale
+		}
+
+		self.precipitation = Storage::from_values(Element::Face, p.depth, precipitation)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use glam::Vec3;
+	use crate::object::planet::{Planet, Descriptor};
+	use crate::object::planet::layer::{Elevation, Field};
+	use crate::topology::Cell;
+	use super::{Climate, Parameters, insolation, daily_insolation, wind};
+
+	#[test]
+	fn bands() {
+		// Higher tilt brings more heat to the poles.
+		assert!(insolation(1.5, 0.8) > insolation(1.5, 0.4));
+
+		// Polar day and night.
+		assert_eq!(daily_insolation(1.4, -0.4), 0.0);
+		assert!(daily_insolation(1.4, 0.4) > daily_insolation(0.0, 0.4));
+		assert!((daily_insolation(0.0, 0.0) - 4.0 / std::f32::consts::PI).abs() < 1e-5);
+		assert!(insolation(0.0, 0.4) > insolation(1.0, 0.4));
+
+		// Trade winds blow westward, westerlies eastward.
+		assert!(wind(Vec3::new(1.0, 0.0, 0.2)).y < 0.0);
+		assert!(wind(Vec3::new(1.0, 0.0, 1.0)).y > 0.0);
+
+		let mut planet = Planet::from_seed(2, Descriptor::default());
+		planet.add_layer(Elevation::default()).unwrap();
+		planet.add_layer(Climate::new(Parameters { depth: 3, ..Parameters::default() })).unwrap();
+		planet.generate().unwrap();
+
+		let climate = planet.layer::<Climate>(Climate::NAME).unwrap();
+		let mean: f32 = Cell::all(3).map(|c| c.area() * climate.precipitation(c)).sum::<f32>() / (4.0 * std::f32::consts::PI);
+		assert!((mean - planet.descriptor().precipitation).abs() < 1.0);
+		assert!(Cell::all(3).all(|c| climate.precipitation(c) >= 0.0));
+
+		// Altitude only cools.
+		let d = planet.descriptor();
+		for c in Cell::all(3) {
+			let sea_level = d.mean_temperature + climate.parameters().contrast * (insolation(c.center().z.asin(), d.axial_tilt) - 1.0);
+			assert!(climate.sample_cell(c).temperature <= sea_level + 1e-3)
+		}
+	}
+}
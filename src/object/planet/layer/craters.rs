@@ -0,0 +1,307 @@
+use std::collections::HashMap;
+use glam::Vec3;
+use crate::random::Rng;
+use crate::topology::{Cell, Cap, bounds::angle};
+use super::{Layer, Context, Field};
+
+/// Craters layer parameters.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Parameters {
+	/// Number of craters.
+	pub count: u32,
+
+	/// Radius of the smallest craters, in m.
+	pub min_radius: f32,
+
+	/// Radius of the largest craters, in m.
+	pub max_radius: f32,
+
+	/// Exponent of the cumulative size distribution (the number of craters larger than `r` is proportional to `r^-exponent`).
+	pub exponent: f32,
+
+	/// Depth of simple craters, relative to their diameter.
+	pub depth_ratio: f32,
+
+	/// Rim height, relative to the crater depth.
+	pub rim_ratio: f32,
+
+	/// Radius above which craters are complex (flat floor and central peak), in m.
+	pub complex_radius: f32,
+
+	/// Extent of the ejecta blanket, relative to the crater radius.
+	pub ejecta: f32
+}
+
+impl Default for Parameters {
+	/// Moon-like craters.
+	fn default() -> Parameters {
+		Parameters {
+			count: 500,
+			min_radius: 5_000.0,
+			max_radius: 150_000.0,
+			exponent: 2.0,
+			depth_ratio: 0.2,
+			rim_ratio: 0.2,
+			complex_radius: 10_000.0,
+			ejecta: 3.0
+		}
+	}
+}
+
+/// Impact crater.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Crater {
+	/// Direction of the crater center (unit vector).
+	pub center: Vec3,
+
+	/// Rim radius, in m.
+	pub radius: f32,
+
+	/// Depth of the floor below the surroundings, in m.
+	pub depth: f32,
+
+	/// Rim height above the surroundings, in m.
+	pub rim: f32,
+
+	/// Central peak height above the floor (0 for simple craters), in m.
+	pub peak: f32,
+
+	/// Radius of the flat floor, relative to the crater radius (0 for simple craters).
+	pub floor: f32,
+
+	/// Extent of the ejecta blanket, relative to the crater radius.
+	pub ejecta: f32
+}
+
+fn smoothstep(t: f32) -> f32 {
+	let t = t.clamp(0.0, 1.0);
+	t * t * (3.0 - 2.0 * t)
+}
+
+impl Crater {
+	/// Crater of the given radius, with the shape given by the parameters.
+	///
+	/// Complex craters get shallower than simple ones as they grow.
+	pub fn new(center: Vec3, radius: f32, p: &Parameters) -> Crater {
+		let transition = p.complex_radius;
+		let (depth, complexity) = if radius > transition {
+			(p.depth_ratio * 2.0 * transition * libm::powf(radius / transition, 0.3), 1.0 - transition / radius)
+		} else {
+			(p.depth_ratio * 2.0 * radius, 0.0)
+		};
+
+		Crater {
+			center: center.normalize(),
+			radius,
+			depth,
+			rim: p.rim_ratio * depth,
+			peak: 0.5 * depth * complexity,
+			floor: 0.5 * complexity,
+			ejecta: p.ejecta
+		}
+	}
+
+	/// Altitude variation at the given distance from the center (in m),
+	/// and the proportion of the previous relief covered by the crater (between 0 and 1).
+	pub fn profile(&self, distance: f32) -> (f32, f32) {
+		let x = distance / self.radius;
+		if x < 1.0 {
+			// Bowl, with a flat floor and a central peak for complex craters.
+			let t = ((x - self.floor) / (1.0 - self.floor)).max(0.0);
+			let mut h = -self.depth + (self.depth + self.rim) * t * t;
+			let peak = 0.5 * self.floor;
+			if x < peak {
+				h += self.peak * (1.0 + libm::cosf(std::f32::consts::PI * x / peak)) / 2.0
+			}
+
+			(h, 1.0)
+		} else if x < self.ejecta {
+			// Ejecta thickness decreases as the cube of the distance.
+			let w = 1.0 / (x * x * x) * (1.0 - smoothstep((x - 1.0) / (self.ejecta - 1.0)));
+			(self.rim * w, w)
+		} else {
+			(0.0, 0.0)
+		}
+	}
+}
+
+/// Altitude variations caused by the craters.
+#[derive(Clone)]
+pub struct Impacts {
+	/// Radius of the body, in m.
+	radius: f32,
+
+	/// Craters, from the oldest to the youngest.
+	craters: Vec<Crater>,
+
+	/// Craters reaching each cell of the bucket depth.
+	buckets: HashMap<u32, Vec<u32>>,
+
+	/// Deepest floor and highest rim.
+	bounds: (f32, f32)
+}
+
+/// Depth of the cells used to find the craters near a point.
+const BUCKET_DEPTH: u32 = 3;
+
+impl Impacts {
+	/// Scatter craters over a body of the given radius.
+	pub fn new(rng: &mut Rng, radius: f32, p: &Parameters) -> Impacts {
+		// Inverse of the truncated power law distribution.
+		let ratio = libm::powf(p.min_radius / p.max_radius, p.exponent);
+		let craters: Vec<Crater> = (0..p.count).map(|_| {
+			let center = rng.unit_vector();
+			let r = p.min_radius * libm::powf(1.0 - rng.next_f32() * (1.0 - ratio), -1.0 / p.exponent);
+			Crater::new(center, r, p)
+		}).collect();
+
+		let mut buckets: HashMap<u32, Vec<u32>> = HashMap::new();
+		for (i, c) in craters.iter().enumerate() {
+			let cap = Cap::new(c.center, c.radius * c.ejecta / radius, 1.0, 1.0);
+			let mut cells: Vec<Cell> = Cell::all(0).collect();
+			while let Some(cell) = cells.pop() {
+				if cap.intersects(&cell.cap(1.0, 1.0)) {
+					if cell.depth() == BUCKET_DEPTH {
+						buckets.entry(cell.index()).or_default().push(i as u32)
+					} else {
+						cells.extend(cell.children().iter())
+					}
+				}
+			}
+		}
+
+		// Keep the age order in each bucket.
+		for craters in buckets.values_mut() {
+			craters.sort()
+		}
+
+		// Ejecta blend the previous relief toward the rim height, so the relief stays between the floors and rims.
+		let bounds = craters.iter().fold((0.0f32, 0.0f32), |(min, max), c| (min.min(-c.depth), max.max(c.rim)));
+
+		Impacts {
+			radius,
+			craters,
+			buckets,
+			bounds
+		}
+	}
+
+	/// Craters, from the oldest to the youngest.
+	pub fn craters(&self) -> &[Crater] {
+		&self.craters
+	}
+}
+
+impl Field<f32> for Impacts {
+	/// Altitude variation in the given direction, in m.
+	///
+	/// Younger craters cover the older ones.
+	fn sample(&self, dir: Vec3) -> f32 {
+		let dir = dir.normalize();
+		let mut h = 0.0;
+		if let Some(craters) = self.buckets.get(&Cell::locate(dir, BUCKET_DEPTH).index()) {
+			for c in craters {
+				let c = &self.craters[*c as usize];
+				let (dh, w) = c.profile(angle(c.center, dir) * self.radius);
+				h = h * (1.0 - w) + dh
+			}
+		}
+
+		h
+	}
+
+	fn bounds(&self) -> Option<(f32, f32)> {
+		Some(self.bounds)
+	}
+}
+
+/// Craters layer.
+///
+/// Scatters impact craters (for airless bodies), as a displacement source of the elevation.
+pub struct Craters {
+	parameters: Parameters,
+	impacts: Option<Impacts>
+}
+
+impl Craters {
+	pub const NAME: &'static str = "craters";
+
+	pub fn new(parameters: Parameters) -> Craters {
+		Craters {
+			parameters,
+			impacts: None
+		}
+	}
+
+	pub fn parameters(&self) -> &Parameters {
+		&self.parameters
+	}
+
+	/// Generated craters.
+	///
+	/// Panics if the layer has not been evaluated.
+	pub fn impacts(&self) -> &Impacts {
+		self.impacts.as_ref().expect("craters layer not evaluated")
+	}
+}
+
+impl Default for Craters {
+	fn default() -> Craters {
+		Craters::new(Parameters::default())
+	}
+}
+
+impl Layer for Craters {
+	fn name(&self) -> &str {
+		Craters::NAME
+	}
+
+	fn evaluate(&mut self, context: &mut Context) {
+		self.impacts = Some(Impacts::new(&mut context.rng(), context.descriptor.radius, &self.parameters))
+	}
+
+	fn displacement(&self) -> Option<Box<dyn Field<f32>>> {
+		self.impacts.clone().map(|i| Box::new(i) as Box<dyn Field<f32>>)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use glam::Vec3;
+	use crate::object::planet::{Planet, Descriptor};
+	use crate::object::planet::layer::{Elevation, Field};
+	use super::{Craters, Crater, Parameters};
+
+	#[test]
+	fn craters() {
+		let p = Parameters::default();
+		let simple = Crater::new(Vec3::unit_z(), 5_000.0, &p);
+		assert!(simple.profile(0.0).0 < 0.0);
+		assert!((simple.profile(5_000.0).0 - simple.rim).abs() < 1e-3);
+		assert_eq!(simple.profile(20_000.0), (0.0, 0.0));
+
+		let complex = Crater::new(Vec3::unit_z(), 100_000.0, &p);
+		assert!(complex.depth / complex.radius < simple.depth / simple.radius);
+		assert!(complex.profile(0.0).0 > complex.profile(30_000.0).0);
+
+		let descriptor = Descriptor { radius: 1_737_000.0, ..Descriptor::default() };
+		let mut moon = Planet::from_seed(7, descriptor);
+		let mut elevation = Elevation::default();
+		elevation.add_source(Craters::NAME);
+		moon.add_layer(Craters::default()).unwrap();
+		moon.add_layer(elevation).unwrap();
+		moon.generate().unwrap();
+
+		let craters = moon.layer::<Craters>(Craters::NAME).unwrap().impacts();
+		assert_eq!(craters.craters().len(), p.count as usize);
+		assert!(craters.craters().iter().all(|c| c.radius >= p.min_radius && c.radius <= p.max_radius));
+
+		// Small craters are more frequent.
+		let small = craters.craters().iter().filter(|c| c.radius < 2.0 * p.min_radius).count();
+		assert!(small > craters.craters().len() / 2);
+
+		// The youngest crater is intact.
+		let youngest = craters.craters().last().unwrap();
+		assert!((craters.sample(youngest.center) - youngest.profile(0.0).0).abs() < 1e-3)
+	}
+}
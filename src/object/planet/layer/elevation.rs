@@ -0,0 +1,186 @@
+use glam::Vec3;
+use crate::noise::{Noise, Fractal, Octaves};
+use super::{Layer, Context, Field};
+
+/// Elevation layer parameters.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Parameters {
+	pub fractal: Fractal,
+
+	/// Octaves of the noise, with frequencies relative to the planet radius.
+	pub octaves: Octaves,
+
+	/// Maximum deviation from the mean altitude, in m.
+	pub amplitude: f32,
+
+	/// Mean altitude, in m.
+	pub offset: f32
+}
+
+impl Default for Parameters {
+	fn default() -> Parameters {
+		Parameters {
+			fractal: Fractal::Warped { strength: 0.3 },
+			octaves: Octaves {
+				frequency: 2.0,
+				..Octaves::default()
+			},
+			amplitude: 8_000.0,
+			offset: 0.0
+		}
+	}
+}
+
+/// Elevation layer.
+///
+/// Gives the altitude of the ground relative to the planet radius, in m.
+/// It is a pure function of the direction, so it can be evaluated at any position
+/// and gives the same altitude to a vertex whatever the level of detail.
+///
+/// The displacements of the source layers (e.g. [`Tectonics`](super::Tectonics)) are added to the noise.
+/// Sources may read the elevation themselves (e.g. [`Rivers`](super::Rivers)):
+/// they are then evaluated after it, and see the elevation without their own displacement.
+pub struct Elevation {
+	parameters: Parameters,
+
+	/// Names of the layers whose displacement is added to the elevation.
+	sources: Vec<String>,
+
+	/// Displacements collected from the source layers, by source name.
+	displacements: Vec<(String, Box<dyn Field<f32>>)>,
+
+	/// Noise, drawn from the layer random stream on evaluation.
+	noise: Option<Noise>
+}
+
+impl Elevation {
+	pub const NAME: &'static str = "elevation";
+
+	pub fn new(parameters: Parameters) -> Elevation {
+		Elevation {
+			parameters,
+			sources: Vec::new(),
+			displacements: Vec::new(),
+			noise: None
+		}
+	}
+
+	pub fn parameters(&self) -> &Parameters {
+		&self.parameters
+	}
+
+	pub fn is_evaluated(&self) -> bool {
+		self.noise.is_some()
+	}
+
+	/// Add the displacement of the given layer to the elevation.
+	pub fn add_source(&mut self, name: &str) {
+		self.sources.push(name.to_string())
+	}
+
+	/// Checks if the displacement of the given layer is added to the elevation.
+	pub fn has_source(&self, name: &str) -> bool {
+		self.sources.iter().any(|s| s == name)
+	}
+
+	/// Altitude of the vertex splitting the edge `ab` during refinement.
+	///
+	/// The midpoint is computed from the directions of `a` and `b` (as in [`Cell`](crate::topology::Cell) subdivision),
+	/// so that it does not depend on their displacement.
+	pub fn midpoint(&self, a: Vec3, b: Vec3) -> f32 {
+		self.sample(((a.normalize() + b.normalize()) / 2.0).normalize())
+	}
+
+	/// Displaced position of the ground in the given direction, for a planet of the given radius.
+	pub fn displace(&self, dir: Vec3, radius: f32) -> Vec3 {
+		let dir = dir.normalize();
+		dir * (radius + self.sample(dir))
+	}
+}
+
+impl Default for Elevation {
+	fn default() -> Elevation {
+		Elevation::new(Parameters::default())
+	}
+}
+
+impl Layer for Elevation {
+	fn name(&self) -> &str {
+		Elevation::NAME
+	}
+
+	fn inputs(&self) -> Vec<String> {
+		self.sources.clone()
+	}
+
+	fn sources(&self) -> Vec<String> {
+		self.sources.clone()
+	}
+
+	fn evaluate(&mut self, context: &mut Context) {
+		let p = &self.parameters;
+		self.noise = Some(Noise::new(&mut context.rng(), p.fractal, p.octaves));
+		self.displacements.clear()
+	}
+
+	fn collect(&mut self, source: &str, displacement: Option<Box<dyn Field<f32>>>) {
+		self.displacements.retain(|(s, _)| s != source);
+		if let Some(d) = displacement {
+			self.displacements.push((source.to_string(), d))
+		}
+	}
+}
+
+impl Field<f32> for Elevation {
+	/// Altitude in the given direction.
+	///
+	/// Panics if the layer has not been evaluated.
+	fn sample(&self, dir: Vec3) -> f32 {
+		let noise = self.noise.as_ref().expect("elevation layer not evaluated");
+		let dir = dir.normalize();
+		let displacement: f32 = self.displacements.iter().map(|(_, d)| d.sample(dir)).sum();
+		self.parameters.offset + self.parameters.amplitude * noise.sample(dir) + displacement
+	}
+
+	/// Noise amplitude around the offset, plus the bounds of the collected displacements.
+	///
+	/// `None` if a displacement has unknown bounds.
+	fn bounds(&self) -> Option<(f32, f32)> {
+		let p = &self.parameters;
+		self.displacements.iter().try_fold((p.offset - p.amplitude, p.offset + p.amplitude), |(min, max), (_, d)| {
+			let (low, high) = d.bounds()?;
+			Some((min + low, max + high))
+		})
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::object::planet::{Planet, Descriptor};
+	use crate::topology::Cell;
+	use super::super::Field;
+	use super::Elevation;
+
+	#[test]
+	fn deterministic() {
+		let mut a = Planet::from_seed(12, Descriptor::default());
+		a.add_layer(Elevation::default()).unwrap();
+		a.generate().unwrap();
+
+		let mut b = Planet::from_seed(12, Descriptor::default());
+		b.add_layer(Elevation::default()).unwrap();
+		b.generate().unwrap();
+
+		let a = a.layer::<Elevation>(Elevation::NAME).unwrap();
+		let b = b.layer::<Elevation>(Elevation::NAME).unwrap();
+		for cell in Cell::all(2) {
+			let [x, y, _] = cell.corners();
+			assert_eq!(a.sample_cell(cell), b.sample_cell(cell));
+			assert!(a.sample_cell(cell).abs() <= a.parameters().amplitude);
+
+			// Refinement vertices.
+			let child = cell.children()[0].corners();
+			assert!((a.midpoint(x * 2.0, y * 3.0) - a.sample(child[1])).abs() < 1.0)
+		}
+	}
+}
@@ -0,0 +1,325 @@
+use std::collections::BTreeMap;
+use glam::Vec3;
+use crate::random::Rng;
+use crate::topology::{Cell, Mesh, Storage, bounds::angle};
+use super::{Layer, Context, Field, Elevation};
+
+/// Erosion layer parameters.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Parameters {
+	/// Depth of the mesh eroded on evaluation.
+	pub depth: u32,
+
+	/// Depth of the mesh eroded in a refined cell, relative to the cell depth.
+	///
+	/// No erosion is done on refinement if `None`.
+	pub refine_depth: Option<u32>,
+
+	/// Number of droplets, per vertex.
+	pub droplets: f32,
+
+	/// Maximum number of steps of a droplet.
+	pub steps: u32,
+
+	/// Sediment capacity of a droplet, relative to the height it falls.
+	pub capacity: f32,
+
+	/// Proportion of the missing sediment eroded at each step.
+	pub erosion: f32,
+
+	/// Proportion of the excess sediment deposited at each step.
+	pub deposition: f32,
+
+	/// Proportion of the water evaporated at each step.
+	pub evaporation: f32,
+
+	/// Number of thermal erosion iterations.
+	pub thermal_iterations: u32,
+
+	/// Maximum stable slope (talus angle tangent).
+	pub talus: f32,
+
+	/// Proportion of the unstable material moved at each thermal iteration.
+	pub thermal_rate: f32
+}
+
+impl Default for Parameters {
+	fn default() -> Parameters {
+		Parameters {
+			depth: 6,
+			refine_depth: None,
+			droplets: 1.0,
+			steps: 64,
+			capacity: 0.5,
+			erosion: 0.3,
+			deposition: 0.3,
+			evaporation: 0.02,
+			thermal_iterations: 8,
+			talus: 0.6,
+			thermal_rate: 0.5
+		}
+	}
+}
+
+/// Altitude variations caused by the erosion, interpolated over the eroded meshes.
+///
+/// There is one patch for the whole planet, and one per refined cell.
+#[derive(Clone, Default)]
+pub struct Eroded {
+	patches: BTreeMap<Option<Cell>, Storage<f32>>
+}
+
+impl Eroded {
+	/// Altitude variation of the given patch (`None` for the whole planet).
+	fn patch(&self, key: Option<Cell>, dir: Vec3) -> f32 {
+		self.patches.get(&key).and_then(|p| p.sample(dir)).unwrap_or(0.0)
+	}
+}
+
+impl Field<f32> for Eroded {
+	fn sample(&self, dir: Vec3) -> f32 {
+		self.patches.values().filter_map(|p| p.sample(dir)).sum()
+	}
+
+	/// Sum of the patches bounds, each patch being interpolated between its vertex values.
+	fn bounds(&self) -> Option<(f32, f32)> {
+		Some(self.patches.values().fold((0.0, 0.0), |(min, max), p| {
+			let (low, high) = p.values().fold((0.0f32, 0.0f32), |(l, h), v| (l.min(*v), h.max(*v)));
+			(min + low, max + high)
+		}))
+	}
+}
+
+/// Erode the given heights (in m) over a mesh, using a planet of the given radius and sea level.
+///
+/// Border vertices of the mesh are left untouched so that neighbor meshes remain continuous.
+pub fn erode(mesh: &Mesh, heights: &mut [f32], radius: f32, sea_level: f32, p: &Parameters, rng: &mut Rng) {
+	thermal(mesh, heights, radius, p);
+	hydraulic(mesh, heights, sea_level, p, rng);
+}
+
+/// Thermal erosion: material slides down slopes steeper than the talus angle.
+fn thermal(mesh: &Mesh, heights: &mut [f32], radius: f32, p: &Parameters) {
+	let n = heights.len();
+	let mut deltas = vec![0.0f32; n];
+	for _ in 0..p.thermal_iterations {
+		for d in deltas.iter_mut() {
+			*d = 0.0
+		}
+
+		// Moves are computed from the same state, so the vertices order does not matter.
+		for v in 0..n as u32 {
+			let h = heights[v as usize];
+			let pv = mesh.vertex(v);
+			for w in mesh.neighbors(v) {
+				let distance = angle(pv, mesh.vertex(*w)) * radius;
+				let excess = h - heights[*w as usize] - p.talus * distance;
+				if excess > 0.0 {
+					let amount = p.thermal_rate * excess / (2.0 * mesh.neighbors(v).len() as f32);
+					deltas[v as usize] -= amount;
+					deltas[*w as usize] += amount
+				}
+			}
+		}
+
+		for v in 0..n {
+			if !mesh.is_boundary(v as u32) {
+				heights[v] += deltas[v]
+			}
+		}
+	}
+}
+
+/// Hydraulic erosion: droplets erode the ground while flowing down, and deposit their sediment.
+fn hydraulic(mesh: &Mesh, heights: &mut [f32], sea_level: f32, p: &Parameters, rng: &mut Rng) {
+	let n = heights.len() as u32;
+	let droplets = (p.droplets * n as f32) as u32;
+	for _ in 0..droplets {
+		let mut v = rng.below(n);
+		let mut water = 1.0f32;
+		let mut sediment = 0.0f32;
+		for _ in 0..p.steps {
+			if mesh.is_boundary(v) || heights[v as usize] < sea_level {
+				break
+			}
+
+			let h = heights[v as usize];
+			let lowest = mesh.neighbors(v).iter().copied().min_by(|a, b| heights[*a as usize].partial_cmp(&heights[*b as usize]).unwrap().then(a.cmp(b))).unwrap();
+			let drop = h - heights[lowest as usize];
+			if drop <= 0.0 {
+				// Pit: fill it, up to its lowest neighbor.
+				heights[v as usize] += sediment.min(-drop);
+				break
+			}
+
+			let capacity = p.capacity * water * drop;
+			if sediment > capacity {
+				let amount = p.deposition * (sediment - capacity);
+				heights[v as usize] += amount;
+				sediment -= amount
+			} else {
+				// Never dig below the next vertex.
+				let amount = (p.erosion * (capacity - sediment)).min(drop * 0.5);
+				heights[v as usize] -= amount;
+				sediment += amount
+			}
+
+			water *= 1.0 - p.evaporation;
+			v = lowest
+		}
+	}
+}
+
+/// Erosion layer.
+///
+/// Erodes the elevation over the whole planet at a given depth on evaluation,
+/// and optionally in the refined cells at finer depths.
+/// The altitude variations are added to the elevation when it uses this layer as a source.
+///
+/// Refining a cell again replaces its previous erosion.
+pub struct Erosion {
+	parameters: Parameters,
+	eroded: Eroded
+}
+
+impl Erosion {
+	pub const NAME: &'static str = "erosion";
+
+	pub fn new(parameters: Parameters) -> Erosion {
+		Erosion {
+			parameters,
+			eroded: Eroded::default()
+		}
+	}
+
+	pub fn parameters(&self) -> &Parameters {
+		&self.parameters
+	}
+
+	/// Altitude variations caused by the erosion.
+	pub fn eroded(&self) -> &Eroded {
+		&self.eroded
+	}
+
+	/// Erode the given mesh, replacing the patch of the given key.
+	fn erode_mesh(&mut self, context: &mut Context, key: Option<Cell>, mesh: Mesh, mut rng: Rng) {
+		let initial: Vec<f32> = {
+			let elevation = context.layer::<Elevation>(Elevation::NAME).unwrap();
+
+			// Do not erode the previous erosion of the same patch again.
+			let eroded = elevation.has_source(Erosion::NAME);
+			mesh.vertices().iter().map(|v| elevation.sample(*v) - if eroded { self.eroded.patch(key, *v) } else { 0.0 }).collect()
+		};
+
+		let mut heights = initial.clone();
+		erode(&mesh, &mut heights, context.descriptor.radius, context.descriptor.sea_level, &self.parameters, &mut rng);
+
+		let deltas: Vec<f32> = heights.iter().zip(&initial).map(|(h, i)| h - i).collect();
+		self.eroded.patches.insert(key, Storage::from_mesh(&mesh, &deltas));
+	}
+}
+
+impl Default for Erosion {
+	fn default() -> Erosion {
+		Erosion::new(Parameters::default())
+	}
+}
+
+impl Layer for Erosion {
+	fn name(&self) -> &str {
+		Erosion::NAME
+	}
+
+	fn inputs(&self) -> Vec<String> {
+		vec![Elevation::NAME.to_string()]
+	}
+
+	fn evaluate(&mut self, context: &mut Context) {
+		self.eroded = Eroded::default();
+		let rng = context.rng();
+		self.erode_mesh(context, None, Mesh::new(self.parameters.depth), rng)
+	}
+
+	fn refine(&mut self, context: &mut Context, cell: Cell) {
+		if let Some(depth) = self.parameters.refine_depth {
+			let rng = context.cell_rng(cell);
+			self.erode_mesh(context, Some(cell), Mesh::region(cell, cell.depth() + depth), rng)
+		}
+	}
+
+	fn displacement(&self) -> Option<Box<dyn Field<f32>>> {
+		Some(Box::new(self.eroded.clone()))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::object::planet::{Planet, Descriptor};
+	use crate::object::planet::layer::{Elevation, Field};
+	use crate::random::Rng;
+	use crate::topology::{Cell, Mesh};
+	use super::{erode, Erosion, Parameters};
+
+	#[test]
+	fn erosion() {
+		let mesh = Mesh::region(Cell::new(2, 5), 5);
+		let radius = 6_371_000.0;
+
+		// Noisy cone.
+		let top = Cell::new(2, 5).center();
+		let mut rng = Rng::new(0);
+		let initial: Vec<f32> = mesh.vertices().iter().map(|v| 3000.0 - v.dot(top).acos() * radius * 0.05 + rng.range(0.0, 500.0)).collect();
+
+		let p = Parameters::default();
+
+		let mut a = initial.clone();
+		erode(&mesh, &mut a, radius, f32::NEG_INFINITY, &p, &mut Rng::new(1));
+		let mut b = initial.clone();
+		erode(&mesh, &mut b, radius, f32::NEG_INFINITY, &p, &mut Rng::new(1));
+		assert_eq!(a, b);
+
+		let mut changed = 0;
+		for v in 0..mesh.vertices().len() {
+			if mesh.is_boundary(v as u32) {
+				assert_eq!(a[v], initial[v])
+			} else if a[v] != initial[v] {
+				changed += 1
+			}
+		}
+		assert!(changed > 0);
+
+		// Erosion smooths the terrain.
+		let roughness = |h: &[f32]| (0..h.len() as u32).map(|v| mesh.neighbors(v).iter().map(|w| (h[v as usize] - h[*w as usize]).abs()).sum::<f32>()).sum::<f32>();
+		assert!(roughness(&a) < roughness(&initial))
+	}
+
+	#[test]
+	fn refine() {
+		let mut planet = Planet::from_seed(5, Descriptor::default());
+		let mut elevation = Elevation::default();
+		elevation.add_source(Erosion::NAME);
+		planet.add_layer(elevation).unwrap();
+		planet.add_layer(Erosion::new(Parameters { depth: 3, refine_depth: Some(3), ..Parameters::default() })).unwrap();
+		planet.generate().unwrap();
+
+		let cell = Cell::new(2, 7);
+		let points: Vec<_> = Mesh::region(cell, 5).vertices().to_vec();
+		let sample = |planet: &Planet| -> Vec<f32> {
+			let elevation = planet.layer::<Elevation>(Elevation::NAME).unwrap();
+			points.iter().map(|p| elevation.sample(*p)).collect()
+		};
+
+		let generated = sample(&planet);
+		planet.refine(cell).unwrap();
+		let refined = sample(&planet);
+		assert_ne!(refined, generated);
+
+		// Refining again replaces the previous erosion of the cell.
+		planet.refine(cell).unwrap();
+		assert!(sample(&planet).iter().zip(&refined).all(|(a, b)| (a - b).abs() < 1e-2));
+
+		// So does generating again.
+		planet.generate().unwrap();
+		assert_eq!(sample(&planet), generated);
+	}
+}
@@ -1,3 +1,426 @@
+//! Planet layers.
+//!
+//! Each layer holds one aspect of the planet (topology, altitude, biomes, etc.),
+//! possibly computed from other layers.
+//! Layers are stored in a [`Registry`] which evaluates them in dependency order.
+
+use std::any::Any;
+use std::fmt;
+use glam::Vec3;
+use crate::location::Position;
+use crate::topology::Cell;
+use crate::random::Rng;
+use super::Descriptor;
+
 pub mod topology;
+pub mod elevation;
+pub mod tectonics;
+pub mod zones;
+pub mod rivers;
+pub mod climate;
+pub mod biomes;
+pub mod erosion;
+pub mod ocean;
+pub mod craters;
+
+pub use topology::Topology;
+pub use elevation::Elevation;
+pub use tectonics::Tectonics;
+pub use zones::Zones;
+pub use rivers::Rivers;
+pub use climate::Climate;
+pub use biomes::Biomes;
+pub use erosion::Erosion;
+pub use ocean::Ocean;
+pub use craters::Craters;
+
+/// Planet layer.
+pub trait Layer: AsAny {
+	/// Name of the layer, used by other layers to refer to it.
+	fn name(&self) -> &str;
+
+	/// Names of the layers this layer reads from.
+	fn inputs(&self) -> Vec<String> {
+		Vec::new()
+	}
+
+	/// Generate the layer content.
+	///
+	/// Every input layer has already been evaluated,
+	/// and is accessible through the context.
+	fn evaluate(&mut self, context: &mut Context);
+
+	/// Refine the layer content in the given cell,
+	/// when the topology is refined.
+	fn refine(&mut self, _context: &mut Context, _cell: Cell) {
+		// nothing to do by default.
+	}
+
+	/// Altitude displacement (in m) this layer contributes to the elevation, if any.
+	///
+	/// It is called once the layer is evaluated,
+	/// and returns a self-contained field that does not borrow the layer.
+	fn displacement(&self) -> Option<Box<dyn Field<f32>>> {
+		None
+	}
+
+	/// Names of the layers whose displacement this layer collects (see [`collect`](Layer::collect)).
+	///
+	/// A source which also reads this layer is evaluated after it,
+	/// e.g. rivers carving into the elevation they flow over.
+	fn sources(&self) -> Vec<String> {
+		Vec::new()
+	}
+
+	/// Receive the displacement of the given source layer, each time it is evaluated or refined.
+	fn collect(&mut self, _source: &str, _displacement: Option<Box<dyn Field<f32>>>) {
+		// nothing to do by default.
+	}
+}
+
+/// Helper trait to downcast layers.
+pub trait AsAny: Any {
+	fn as_any(&self) -> &dyn Any;
+
+	fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Any> AsAny for T {
+	fn as_any(&self) -> &dyn Any {
+		self
+	}
+
+	fn as_any_mut(&mut self) -> &mut dyn Any {
+		self
+	}
+}
+
+/// Value defined on the whole surface of the planet.
+pub trait Field<T> {
+	/// Sample the field in the given direction (from the planet center).
+	fn sample(&self, dir: Vec3) -> T;
+
+	/// Sample the field at the given cell.
+	fn sample_cell(&self, cell: Cell) -> T {
+		self.sample(cell.center())
+	}
+
+	/// Sample the field at the given position.
+	fn sample_position(&self, p: &Position<f32>) -> T {
+		self.sample(p.as_unit().into())
+	}
+
+	/// Lowest and highest sampled values, if known.
+	///
+	/// Displacement fields give them so that the relief can be bounded.
+	fn bounds(&self) -> Option<(T, T)> {
+		None
+	}
+}
+
+/// Layer registry error.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+	/// A layer with the same name is already registered.
+	DuplicateLayer(String),
+
+	/// A layer reads from an unregistered layer.
+	MissingInput {
+		layer: String,
+		input: String
+	},
+
+	/// The given layers depends on each other.
+	Cycle(Vec<String>)
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Error::DuplicateLayer(name) => write!(f, "duplicate layer `{}`", name),
+			Error::MissingInput { layer, input } => write!(f, "missing input `{}` of layer `{}`", input, layer),
+			Error::Cycle(names) => write!(f, "dependency cycle between layers {}", names.join(", "))
+		}
+	}
+}
+
+impl std::error::Error for Error {}
+
+/// Evaluation context of a layer.
+pub struct Context<'a> {
+	/// Global infos about the planet.
+	pub descriptor: &'a Descriptor,
+
+	/// Topology of the planet.
+	pub topology: &'a Topology,
+
+	/// Random stream of the layer being evaluated.
+	rng: Rng,
+
+	/// Registered layers (the one being evaluated is taken out).
+	layers: &'a [Option<Box<dyn Layer>>],
+
+	/// Indexes of the input layers already evaluated, the only ones accessible.
+	inputs: Vec<usize>
+}
+
+impl<'a> Context<'a> {
+	/// Random stream of the layer being evaluated.
+	///
+	/// It only depends on the planet seed and the layer name.
+	pub fn rng(&self) -> Rng {
+		self.rng
+	}
+
+	/// Random stream of the layer being evaluated, in the given cell.
+	///
+	/// It only depends on the planet seed, the layer name and the cell,
+	/// so that cells can be generated in any order.
+	pub fn cell_rng(&self, cell: Cell) -> Rng {
+		self.rng.cell(cell)
+	}
+
+	/// Get the given input layer.
+	///
+	/// Returns `None` if the layer is not one of the declared [`inputs`](Layer::inputs),
+	/// or is not evaluated yet (a source reading the layer being evaluated).
+	pub fn layer<L: Layer>(&self, name: &str) -> Option<&L> {
+		let i = *self.inputs.iter().find(|i| self.layers[**i].as_ref().map(|l| l.name() == name).unwrap_or(false))?;
+		self.layers[i].as_ref().and_then(|l| (**l).as_any().downcast_ref())
+	}
+}
+
+/// Layer registry.
+#[derive(Default)]
+pub struct Registry {
+	layers: Vec<Option<Box<dyn Layer>>>
+}
+
+impl Registry {
+	pub fn new() -> Registry {
+		Registry {
+			layers: Vec::new()
+		}
+	}
+
+	/// Register a new layer.
+	pub fn add<L: Layer>(&mut self, layer: L) -> Result<(), Error> {
+		if self.index(layer.name()).is_some() {
+			Err(Error::DuplicateLayer(layer.name().to_string()))
+		} else {
+			self.layers.push(Some(Box::new(layer)));
+			Ok(())
+		}
+	}
+
+	fn index(&self, name: &str) -> Option<usize> {
+		self.layers.iter().position(|l| l.as_ref().unwrap().name() == name)
+	}
+
+	pub fn get<L: Layer>(&self, name: &str) -> Option<&L> {
+		let i = self.index(name)?;
+		(**self.layers[i].as_ref().unwrap()).as_any().downcast_ref()
+	}
+
+	pub fn get_mut<L: Layer>(&mut self, name: &str) -> Option<&mut L> {
+		let i = self.index(name)?;
+		(**self.layers[i].as_mut().unwrap()).as_any_mut().downcast_mut()
+	}
+
+	/// Names of the registered layers, in registration order.
+	pub fn names(&self) -> impl Iterator<Item = &str> {
+		self.layers.iter().map(|l| l.as_ref().unwrap().name())
+	}
+
+	/// Layers indexes in dependency order.
+	///
+	/// Independent layers are kept in registration order.
+	pub fn order(&self) -> Result<Vec<usize>, Error> {
+		let mut dependencies = Vec::with_capacity(self.layers.len());
+		for layer in &self.layers {
+			let layer = layer.as_ref().unwrap();
+			let sources = layer.sources();
+			let mut inputs = Vec::new();
+			for input in layer.inputs() {
+				match self.index(&input) {
+					Some(i) => {
+						// Sources reading this layer are evaluated after it.
+						let feedback = sources.contains(&input) && self.layers[i].as_ref().unwrap().inputs().iter().any(|n| n == layer.name());
+						if !feedback {
+							inputs.push(i)
+						}
+					},
+					None => return Err(Error::MissingInput {
+						layer: layer.name().to_string(),
+						input
+					})
+				}
+			}
+
+			dependencies.push(inputs)
+		}
+
+		let mut order = Vec::with_capacity(self.layers.len());
+		let mut done = vec![false; self.layers.len()];
+		while order.len() < self.layers.len() {
+			let next = (0..self.layers.len()).find(|&i| !done[i] && dependencies[i].iter().all(|&j| done[j]));
+			match next {
+				Some(i) => {
+					done[i] = true;
+					order.push(i)
+				},
+				None => {
+					let names = (0..self.layers.len()).filter(|&i| !done[i]).map(|i| self.layers[i].as_ref().unwrap().name().to_string()).collect();
+					return Err(Error::Cycle(names))
+				}
+			}
+		}
+
+		Ok(order)
+	}
+
+	/// Evaluate every layer, in dependency order.
+	///
+	/// Each layer gets its own random stream split from `rng`.
+	pub fn evaluate(&mut self, rng: Rng, descriptor: &Descriptor, topology: &Topology) -> Result<(), Error> {
+		let order = self.order()?;
+		for (k, i) in order.iter().enumerate() {
+			self.with_layer(rng, descriptor, topology, *i, &order[..k], |layer, context| layer.evaluate(context));
+			self.exchange(*i, &order[..k])
+		}
+
+		Ok(())
+	}
+
+	/// Refine every layer in the given cell, in dependency order.
+	pub fn refine(&mut self, rng: Rng, descriptor: &Descriptor, topology: &Topology, cell: Cell) -> Result<(), Error> {
+		let order = self.order()?;
+		for (k, i) in order.iter().enumerate() {
+			self.with_layer(rng, descriptor, topology, *i, &order[..k], |layer, context| layer.refine(context, cell));
+			self.exchange(*i, &order[..k])
+		}
+
+		Ok(())
+	}
+
+	/// Exchange the displacements between the layer `i`, just updated, and the `done` layers.
+	fn exchange(&mut self, i: usize, done: &[usize]) {
+		for j in done {
+			self.pass(i, *j);
+			self.pass(*j, i)
+		}
+	}
+
+	/// Pass the displacement of the layer `source` to the layer `collector`, if it collects it.
+	fn pass(&mut self, source: usize, collector: usize) {
+		let name = self.layers[source].as_ref().unwrap().name().to_string();
+		if self.layers[collector].as_ref().unwrap().sources().contains(&name) {
+			let displacement = self.layers[source].as_ref().unwrap().displacement();
+			self.layers[collector].as_mut().unwrap().collect(&name, displacement)
+		}
+	}
+
+	/// Run `f` on the layer `i`, with access to its inputs among the `done` layers.
+	fn with_layer<F>(&mut self, rng: Rng, descriptor: &Descriptor, topology: &Topology, i: usize, done: &[usize], f: F) where F: FnOnce(&mut dyn Layer, &mut Context) {
+		let inputs = self.layers[i].as_ref().unwrap().inputs().iter().filter_map(|name| self.index(name)).filter(|j| done.contains(j)).collect();
+		let mut layer = self.layers[i].take().unwrap();
+
+		{
+			let mut context = Context {
+				descriptor,
+				topology,
+				rng: rng.split_str(layer.name()),
+				layers: &self.layers,
+				inputs
+			};
+
+			f(layer.as_mut(), &mut context);
+		}
+
+		self.layers[i] = Some(layer)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	/// Layer counting its evaluations, and those of its inputs.
+	struct Counter {
+		name: String,
+		inputs: Vec<String>,
+		count: u32
+	}
+
+	impl Counter {
+		fn new(name: &str, inputs: &[&str]) -> Counter {
+			Counter {
+				name: name.to_string(),
+				inputs: inputs.iter().map(|i| i.to_string()).collect(),
+				count: 0
+			}
+		}
+	}
+
+	impl Layer for Counter {
+		fn name(&self) -> &str {
+			&self.name
+		}
+
+		fn inputs(&self) -> Vec<String> {
+			self.inputs.clone()
+		}
+
+		fn evaluate(&mut self, context: &mut Context) {
+			self.count = 1 + self.inputs.iter().map(|i| context.layer::<Counter>(i).unwrap().count).sum::<u32>()
+		}
+	}
+
+	/// Layer looking for another one, without declaring it as an input.
+	struct Probe {
+		target: String,
+		found: bool
+	}
+
+	impl Layer for Probe {
+		fn name(&self) -> &str {
+			"probe"
+		}
+
+		fn evaluate(&mut self, context: &mut Context) {
+			self.found = context.layer::<Counter>(&self.target).is_some()
+		}
+	}
+
+	#[test]
+	fn dependency_order() {
+		let mut registry = Registry::new();
+		registry.add(Counter::new("c", &["b", "a"])).unwrap();
+		registry.add(Counter::new("b", &["a"])).unwrap();
+		registry.add(Counter::new("a", &[])).unwrap();
+		assert_eq!(registry.add(Counter::new("a", &[])), Err(Error::DuplicateLayer("a".to_string())));
+
+		assert_eq!(registry.order(), Ok(vec![2, 1, 0]));
+		registry.evaluate(Rng::new(0), &Descriptor::default(), &Topology::new()).unwrap();
+		assert_eq!(registry.get::<Counter>("c").unwrap().count, 4);
+	}
+
+	#[test]
+	fn undeclared_input() {
+		let mut registry = Registry::new();
+		registry.add(Counter::new("a", &[])).unwrap();
+		registry.add(Probe { target: "a".to_string(), found: true }).unwrap();
+		registry.evaluate(Rng::new(0), &Descriptor::default(), &Topology::new()).unwrap();
+		assert!(!registry.get::<Probe>("probe").unwrap().found);
+	}
+
+	#[test]
+	fn errors() {
+		let mut registry = Registry::new();
+		registry.add(Counter::new("a", &["b"])).unwrap();
+		assert!(matches!(registry.order(), Err(Error::MissingInput { .. })));
 
-pub use topology::Topology;
\ No newline at end of file
+		registry.add(Counter::new("b", &["a"])).unwrap();
+		assert_eq!(registry.order(), Err(Error::Cycle(vec!["a".to_string(), "b".to_string()])));
+	}
+}
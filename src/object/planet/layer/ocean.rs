@@ -0,0 +1,291 @@
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::cmp::Ordering;
+use glam::Vec3;
+use crate::topology::{Cell, Mesh, Storage, Element, bounds::angle};
+use super::{Layer, Context, Field, Elevation};
+
+/// Ocean layer parameters.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Parameters {
+	/// Depth of the cells of the ocean mask.
+	pub depth: u32
+}
+
+impl Default for Parameters {
+	fn default() -> Parameters {
+		Parameters {
+			depth: 6
+		}
+	}
+}
+
+/// Coastline, as a spherical polyline.
+///
+/// The land is on the left of the polyline (seen from outside the planet).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Coastline {
+	/// Points on the unit sphere.
+	pub points: Vec<Vec3>,
+
+	/// Whether the last point is joined to the first one.
+	///
+	/// Coastlines are only open when cut by the border of a region.
+	pub closed: bool
+}
+
+/// Extract the coastlines of an altitude field over a mesh (marching triangles).
+///
+/// The coast points are interpolated on the edges of the mesh from the altitudes of their ends only,
+/// so that neighbor regions give matching coastlines.
+pub fn coastlines<F>(mesh: &Mesh, altitude: F, sea_level: f32) -> Vec<Coastline> where F: Fn(Vec3) -> f32 {
+	let heights: Vec<f32> = mesh.vertices().iter().map(|v| altitude(*v) - sea_level).collect();
+	let land = |v: u32| heights[v as usize] >= 0.0;
+	let key = |a: u32, b: u32| if a < b { (a, b) } else { (b, a) };
+
+	let crossing = |(a, b): (u32, u32)| {
+		// Same order in any mesh, for identical points.
+		let bits = |v: u32| {
+			let p = mesh.vertex(v);
+			[p.x.to_bits(), p.y.to_bits(), p.z.to_bits()]
+		};
+
+		let (a, b) = if bits(a) < bits(b) { (a, b) } else { (b, a) };
+		let (ha, hb) = (heights[a as usize], heights[b as usize]);
+		let t = ha / (ha - hb);
+		(mesh.vertex(a) * (1.0 - t) + mesh.vertex(b) * t).normalize()
+	};
+
+	// Segments, from an edge to another.
+	let mut segments: HashMap<(u32, u32), (u32, u32)> = HashMap::new();
+	for face in mesh.faces() {
+		let count = face.iter().filter(|v| land(**v)).count();
+		if count == 0 || count == 3 {
+			continue
+		}
+
+		for i in 0..3 {
+			let (p, v, n) = (face[(i + 2) % 3], face[i], face[(i + 1) % 3]);
+			if count == 1 && land(v) {
+				segments.insert(key(v, n), key(p, v));
+			} else if count == 2 && !land(v) {
+				segments.insert(key(p, v), key(v, n));
+			}
+		}
+	}
+
+	// Chain the segments, starting with the open polylines.
+	let ends: HashSet<(u32, u32)> = segments.values().copied().collect();
+	let mut starts: Vec<(u32, u32)> = segments.keys().copied().filter(|k| !ends.contains(k)).collect();
+	starts.sort();
+	let mut others: Vec<(u32, u32)> = segments.keys().copied().filter(|k| ends.contains(k)).collect();
+	others.sort();
+
+	let mut lines = Vec::new();
+	for (start, closed) in starts.into_iter().map(|s| (s, false)).chain(others.into_iter().map(|s| (s, true))) {
+		if !segments.contains_key(&start) {
+			continue
+		}
+
+		let mut points = vec![crossing(start)];
+		let mut edge = start;
+		while let Some(next) = segments.remove(&edge) {
+			if next == start {
+				break
+			}
+
+			points.push(crossing(next));
+			edge = next
+		}
+
+		lines.push(Coastline {
+			points,
+			closed
+		})
+	}
+
+	lines
+}
+
+/// Priority queue entry, ordered by lowest distance first.
+#[derive(PartialEq)]
+struct Entry(f32, u32);
+
+impl Eq for Entry {}
+
+impl Ord for Entry {
+	fn cmp(&self, other: &Entry) -> Ordering {
+		other.0.partial_cmp(&self.0).unwrap().then(other.1.cmp(&self.1))
+	}
+}
+
+impl PartialOrd for Entry {
+	fn partial_cmp(&self, other: &Entry) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+/// Ocean layer.
+///
+/// Separates the land from the sea, according to the planet sea level.
+pub struct Ocean {
+	parameters: Parameters,
+
+	/// Sea level, in m.
+	sea_level: f32,
+
+	/// Signed distance of each cell center to the coast, in m (negative in the sea).
+	distances: Storage<f32>
+}
+
+impl Ocean {
+	pub const NAME: &'static str = "ocean";
+
+	pub fn new(parameters: Parameters) -> Ocean {
+		Ocean {
+			parameters,
+			sea_level: 0.0,
+			distances: Storage::sparse(Element::Face, parameters.depth)
+		}
+	}
+
+	pub fn parameters(&self) -> &Parameters {
+		&self.parameters
+	}
+
+	/// Checks if the given cell is in the sea.
+	pub fn is_ocean(&self, cell: Cell) -> bool {
+		self.distance(cell) < 0.0
+	}
+
+	/// Signed distance (along the surface) from the given cell to the coast, in m.
+	///
+	/// It is negative in the sea and positive on land.
+	pub fn distance(&self, cell: Cell) -> f32 {
+		*self.distances.face(cell).unwrap()
+	}
+
+	/// Coastlines of the given region (or of the whole planet), extracted at the given depth.
+	///
+	/// Coastlines of the whole planet are closed.
+	pub fn coastlines(&self, elevation: &Elevation, region: Option<Cell>, depth: u32) -> Vec<Coastline> {
+		let mesh = match region {
+			Some(region) => Mesh::region(region, depth),
+			None => Mesh::new(depth)
+		};
+
+		coastlines(&mesh, |v| elevation.sample(v), self.sea_level)
+	}
+}
+
+impl Default for Ocean {
+	fn default() -> Ocean {
+		Ocean::new(Parameters::default())
+	}
+}
+
+impl Field<bool> for Ocean {
+	/// Whether the given direction is in the sea.
+	fn sample(&self, dir: Vec3) -> bool {
+		self.is_ocean(Cell::locate(dir, self.parameters.depth))
+	}
+}
+
+impl Layer for Ocean {
+	fn name(&self) -> &str {
+		Ocean::NAME
+	}
+
+	fn inputs(&self) -> Vec<String> {
+		vec![Elevation::NAME.to_string()]
+	}
+
+	fn evaluate(&mut self, context: &mut Context) {
+		let depth = self.parameters.depth;
+		let radius = context.descriptor.radius;
+		let elevation = context.layer::<Elevation>(Elevation::NAME).unwrap();
+		self.sea_level = context.descriptor.sea_level;
+
+		let centers: Vec<Vec3> = Cell::all(depth).map(|c| c.center()).collect();
+		let sea: Vec<bool> = centers.iter().map(|c| elevation.sample(*c) < self.sea_level).collect();
+		let neighbors: Vec<[Cell; 3]> = Cell::all(depth).map(|c| c.neighbors()).collect();
+
+		// Distance to the coast, from the cells next to it.
+		let mut distances = vec![f32::INFINITY; centers.len()];
+		let mut queue = BinaryHeap::new();
+		for i in 0..centers.len() {
+			for n in neighbors[i].iter() {
+				let j = n.index() as usize;
+				if sea[i] != sea[j] {
+					// The coast is half way.
+					let d = angle(centers[i], centers[j]) * radius / 2.0;
+					if d < distances[i] {
+						distances[i] = d;
+						queue.push(Entry(d, i as u32))
+					}
+				}
+			}
+		}
+
+		while let Some(Entry(d, i)) = queue.pop() {
+			let i = i as usize;
+			if d > distances[i] {
+				continue
+			}
+
+			for n in neighbors[i].iter() {
+				let j = n.index() as usize;
+				if sea[i] == sea[j] {
+					let e = d + angle(centers[i], centers[j]) * radius;
+					if e < distances[j] {
+						distances[j] = e;
+						queue.push(Entry(e, j as u32))
+					}
+				}
+			}
+		}
+
+		self.distances = Storage::from_values(Element::Face, depth, distances.iter().zip(&sea).map(|(d, s)| if *s { -*d } else { *d }).collect())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use glam::Vec3;
+	use crate::topology::{Cell, Mesh};
+	use crate::object::planet::{Planet, Descriptor};
+	use crate::object::planet::layer::{Elevation, Field};
+	use super::{Ocean, Parameters, coastlines};
+
+	#[test]
+	fn coastline() {
+		// Circular island.
+		let altitude = |v: Vec3| v.z - 0.5;
+		let lines = coastlines(&Mesh::new(4), altitude, 0.0);
+		assert_eq!(lines.len(), 1);
+		assert!(lines[0].closed);
+		assert!(lines[0].points.iter().all(|p| (p.z - 0.5).abs() < 1e-2));
+
+		// Counterclockwise around the island.
+		let p = &lines[0].points;
+		assert!(p[0].cross(p[1]).z > 0.0);
+
+		// Regions give the same coast points.
+		let mut points: Vec<Vec3> = Cell::all(1).flat_map(|r| coastlines(&Mesh::region(r, 4), altitude, 0.0)).flat_map(|l| l.points).collect();
+		points.sort_by(|a, b| a.x.partial_cmp(&b.x).unwrap().then(a.y.partial_cmp(&b.y).unwrap()));
+		points.dedup();
+		assert_eq!(points.len(), lines[0].points.len());
+
+		let mut planet = Planet::from_seed(1, Descriptor { sea_level: 500.0, ..Descriptor::default() });
+		planet.add_layer(Elevation::default()).unwrap();
+		planet.add_layer(Ocean::new(Parameters { depth: 4 })).unwrap();
+		planet.generate().unwrap();
+
+		let ocean = planet.layer::<Ocean>(Ocean::NAME).unwrap();
+		let elevation = planet.layer::<Elevation>(Elevation::NAME).unwrap();
+		for cell in Cell::all(4) {
+			assert_eq!(ocean.is_ocean(cell), elevation.sample(cell.center()) < 500.0)
+		}
+
+		assert!(ocean.coastlines(elevation, None, 4).iter().all(|l| l.closed))
+	}
+}
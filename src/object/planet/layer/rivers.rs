@@ -0,0 +1,445 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use glam::Vec3;
+use crate::topology::{Cell, Mesh, bounds::arc_distance};
+use super::{Layer, Context, Field, Elevation};
+
+/// Rivers layer parameters.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Parameters {
+	/// Depth of the mesh over which water flows.
+	pub depth: u32,
+
+	/// Minimum drainage area of a river, relative to the planet area.
+	pub min_drainage: f32,
+
+	/// River width for a drainage area of 1 km², in m.
+	///
+	/// The width grows with the square root of the drainage area.
+	pub width: f32,
+
+	/// Depth carved by the river, per Strahler order, in m.
+	pub carve_depth: f32,
+
+	/// Width of the valley banks, relative to the river width.
+	pub valley: f32
+}
+
+impl Default for Parameters {
+	fn default() -> Parameters {
+		Parameters {
+			depth: 6,
+			min_drainage: 5e-4,
+			width: 1.0,
+			carve_depth: 10.0,
+			valley: 4.0
+		}
+	}
+}
+
+/// River stretch of constant Strahler order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct River {
+	/// Points of the river on the unit sphere, from upstream to downstream.
+	pub points: Vec<Vec3>,
+
+	/// Strahler order.
+	pub order: u32,
+
+	/// Width at the downstream end, in m.
+	pub width: f32,
+
+	/// Drainage area at the downstream end, in m².
+	pub drainage: f32
+}
+
+/// Priority-flood queue entry, ordered by lowest altitude first.
+#[derive(PartialEq)]
+struct Entry(f32, u32);
+
+impl Eq for Entry {}
+
+impl Ord for Entry {
+	fn cmp(&self, other: &Entry) -> Ordering {
+		other.0.partial_cmp(&self.0).unwrap().then(other.1.cmp(&self.1))
+	}
+}
+
+impl PartialOrd for Entry {
+	fn partial_cmp(&self, other: &Entry) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+/// River bed segment carved into the elevation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Segment {
+	a: Vec3,
+	b: Vec3,
+	width: f32,
+	depth: f32
+}
+
+/// Depth carved by the rivers into the elevation.
+#[derive(Clone)]
+pub struct Carving {
+	radius: f32,
+	valley: f32,
+	segments: Vec<Segment>,
+
+	/// Segments near each cell of the bucket depth.
+	buckets: HashMap<u32, Vec<u32>>
+}
+
+/// Depth of the cells used to find the segments near a point.
+const BUCKET_DEPTH: u32 = 3;
+
+impl Carving {
+	fn new(radius: f32, valley: f32, segments: Vec<Segment>) -> Carving {
+		let mut buckets: HashMap<u32, Vec<u32>> = HashMap::new();
+		for (i, s) in segments.iter().enumerate() {
+			// Cells of the segment ends, and two rings of neighbors.
+			let mut cells = vec![Cell::locate(s.a, BUCKET_DEPTH), Cell::locate(s.b, BUCKET_DEPTH)];
+			for _ in 0..2 {
+				for c in cells.clone() {
+					cells.extend(c.neighbors().iter())
+				}
+
+				cells.sort();
+				cells.dedup();
+			}
+
+			for c in cells {
+				buckets.entry(c.index()).or_default().push(i as u32)
+			}
+		}
+
+		Carving {
+			radius,
+			valley,
+			segments,
+			buckets
+		}
+	}
+}
+
+impl Field<f32> for Carving {
+	/// Carved depth (negative displacement) in the given direction, in m.
+	fn sample(&self, dir: Vec3) -> f32 {
+		let dir = dir.normalize();
+		let mut depth = 0.0f32;
+		if let Some(segments) = self.buckets.get(&Cell::locate(dir, BUCKET_DEPTH).index()) {
+			for s in segments {
+				let s = &self.segments[*s as usize];
+				let d = arc_distance(s.a, s.b, dir) * self.radius;
+				let t = ((d - s.width / 2.0) / (self.valley * s.width)).max(0.0);
+				if t < 1.0 {
+					depth = depth.max(s.depth * (1.0 - t * t * (3.0 - 2.0 * t)))
+				}
+			}
+		}
+
+		-depth
+	}
+
+	fn bounds(&self) -> Option<(f32, f32)> {
+		Some((-self.segments.iter().fold(0.0f32, |d, s| d.max(s.depth)), 0.0))
+	}
+}
+
+/// Rivers layer.
+///
+/// Water flows over the vertices of an icosphere mesh, down to the sea.
+/// Depressions are filled (priority-flood), so that every land vertex drains into the sea.
+/// The rivers are then carved into the elevation, when it uses this layer as a source.
+pub struct Rivers {
+	parameters: Parameters,
+	mesh: Option<Mesh>,
+
+	/// Next vertex downstream (`None` in the sea).
+	receivers: Vec<Option<u32>>,
+
+	/// Drainage area of each vertex, in m².
+	accumulation: Vec<f32>,
+
+	/// Strahler order of each vertex (0 if it is not on a river).
+	orders: Vec<u32>,
+
+	/// Mouth vertex of the basin of each vertex (`None` in the sea).
+	basins: Vec<Option<u32>>,
+
+	rivers: Vec<River>,
+	carving: Option<Carving>
+}
+
+impl Rivers {
+	pub const NAME: &'static str = "rivers";
+
+	pub fn new(parameters: Parameters) -> Rivers {
+		Rivers {
+			parameters,
+			mesh: None,
+			receivers: Vec::new(),
+			accumulation: Vec::new(),
+			orders: Vec::new(),
+			basins: Vec::new(),
+			rivers: Vec::new(),
+			carving: None
+		}
+	}
+
+	pub fn parameters(&self) -> &Parameters {
+		&self.parameters
+	}
+
+	/// Flow mesh.
+	///
+	/// Panics if the layer has not been evaluated.
+	pub fn mesh(&self) -> &Mesh {
+		self.mesh.as_ref().expect("rivers layer not evaluated")
+	}
+
+	/// Next vertex downstream of the given vertex.
+	pub fn receiver(&self, vertex: u32) -> Option<u32> {
+		self.receivers[vertex as usize]
+	}
+
+	/// Drainage area of the given vertex, in m².
+	pub fn accumulation(&self, vertex: u32) -> f32 {
+		self.accumulation[vertex as usize]
+	}
+
+	/// Strahler order of the river through the given vertex (0 if there is none).
+	pub fn order(&self, vertex: u32) -> u32 {
+		self.orders[vertex as usize]
+	}
+
+	/// Flow direction (unit tangent vector) in the given direction, if on land.
+	pub fn flow(&self, dir: Vec3) -> Option<Vec3> {
+		let mesh = self.mesh();
+		let v = mesh.nearest(dir);
+		let p = mesh.vertex(v);
+		self.receiver(v).map(|r| {
+			let d = mesh.vertex(r) - p;
+			(d - p * d.dot(p)).normalize()
+		})
+	}
+
+	/// Drainage basin label in the given direction, if on land.
+	///
+	/// It is the index of the basin mouth vertex.
+	pub fn basin(&self, dir: Vec3) -> Option<u32> {
+		self.basins[self.mesh().nearest(dir) as usize]
+	}
+
+	/// Major rivers, as stretches of constant Strahler order.
+	pub fn rivers(&self) -> &[River] {
+		&self.rivers
+	}
+
+	/// Depth carved by the rivers.
+	pub fn carving(&self) -> Option<&Carving> {
+		self.carving.as_ref()
+	}
+}
+
+impl Default for Rivers {
+	fn default() -> Rivers {
+		Rivers::new(Parameters::default())
+	}
+}
+
+impl Layer for Rivers {
+	fn name(&self) -> &str {
+		Rivers::NAME
+	}
+
+	fn inputs(&self) -> Vec<String> {
+		vec![Elevation::NAME.to_string()]
+	}
+
+	fn evaluate(&mut self, context: &mut Context) {
+		let p = self.parameters;
+		let radius = context.descriptor.radius;
+		let mesh = Mesh::new(p.depth);
+		let n = mesh.vertices().len();
+		let altitudes: Vec<f32> = {
+			let elevation = context.layer::<Elevation>(Elevation::NAME).unwrap();
+			mesh.vertices().iter().map(|v| elevation.sample(*v)).collect()
+		};
+
+		// Priority-flood from the sea (or the lowest vertex if there is none).
+		let mut filled = altitudes.clone();
+		let mut receivers = vec![None; n];
+		let mut visited = vec![false; n];
+		let mut queue = BinaryHeap::new();
+		for v in 0..n {
+			if altitudes[v] < context.descriptor.sea_level {
+				visited[v] = true;
+				queue.push(Entry(altitudes[v], v as u32))
+			}
+		}
+
+		if queue.is_empty() {
+			let lowest = (0..n).min_by(|a, b| altitudes[*a].partial_cmp(&altitudes[*b]).unwrap()).unwrap();
+			visited[lowest] = true;
+			queue.push(Entry(altitudes[lowest], lowest as u32))
+		}
+
+		let mut order = Vec::with_capacity(n);
+		while let Some(Entry(h, v)) = queue.pop() {
+			order.push(v);
+			for w in mesh.neighbors(v) {
+				let w = *w as usize;
+				if !visited[w] {
+					visited[w] = true;
+					// Keep a slope toward the receiver on filled depressions.
+					filled[w] = altitudes[w].max(h + 1e-3);
+					receivers[w] = Some(v);
+					queue.push(Entry(filled[w], w as u32))
+				}
+			}
+		}
+
+		// Drainage area, from upstream to downstream.
+		let vertex_area = 4.0 * std::f32::consts::PI * radius * radius / n as f32;
+		let mut accumulation = vec![vertex_area; n];
+		for v in order.iter().rev() {
+			if let Some(r) = receivers[*v as usize] {
+				accumulation[r as usize] += accumulation[*v as usize]
+			}
+		}
+
+		// Strahler orders, from upstream to downstream.
+		let min_drainage = p.min_drainage * 4.0 * std::f32::consts::PI * radius * radius;
+		let mut orders = vec![0u32; n];
+		let mut upstream = vec![(0u32, 0u32); n];
+		for v in order.iter().rev() {
+			let v = *v as usize;
+			if accumulation[v] < min_drainage || receivers[v].is_none() {
+				continue
+			}
+
+			let (max, count) = upstream[v];
+			orders[v] = if max == 0 { 1 } else if count >= 2 { max + 1 } else { max };
+
+			let r = receivers[v].unwrap() as usize;
+			let (max, count) = upstream[r];
+			upstream[r] = if orders[v] > max { (orders[v], 1) } else if orders[v] == max { (max, count + 1) } else { (max, count) };
+		}
+
+		// Basins, from downstream to upstream.
+		let mut basins = vec![None; n];
+		for v in &order {
+			let v = *v as usize;
+			basins[v] = receivers[v].map(|r| if receivers[r as usize].is_none() { v as u32 } else { basins[r as usize].unwrap() })
+		}
+
+		// River stretches start at sources and where the order changes.
+		let width = |v: usize| p.width * (accumulation[v] / 1e6).sqrt();
+		let mut rivers = Vec::new();
+		let mut segments = Vec::new();
+		for v in &order {
+			let v = *v as usize;
+			if orders[v] == 0 || upstream[v].0 == orders[v] {
+				continue
+			}
+
+			let mut points = vec![mesh.vertex(v as u32)];
+			let mut last = v;
+			while let Some(r) = receivers[last] {
+				let r = r as usize;
+				points.push(mesh.vertex(r as u32));
+				segments.push(Segment {
+					a: mesh.vertex(last as u32),
+					b: mesh.vertex(r as u32),
+					width: width(last),
+					depth: p.carve_depth * orders[last] as f32
+				});
+
+				if orders[r] != orders[v] {
+					break
+				}
+
+				last = r
+			}
+
+			rivers.push(River {
+				points,
+				order: orders[v],
+				width: width(last),
+				drainage: accumulation[last]
+			})
+		}
+
+		let carving = Carving::new(radius, p.valley, segments);
+
+		self.mesh = Some(mesh);
+		self.receivers = receivers;
+		self.accumulation = accumulation;
+		self.orders = orders;
+		self.basins = basins;
+		self.rivers = rivers;
+		self.carving = Some(carving)
+	}
+
+	fn displacement(&self) -> Option<Box<dyn Field<f32>>> {
+		self.carving.clone().map(|c| Box::new(c) as Box<dyn Field<f32>>)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::object::planet::{Planet, Descriptor};
+	use crate::object::planet::layer::{Elevation, Field};
+	use super::{Rivers, Parameters};
+
+	#[test]
+	fn drainage() {
+		let mut planet = Planet::from_seed(4, Descriptor::default());
+		let mut elevation = Elevation::default();
+		elevation.add_source(Rivers::NAME);
+		planet.add_layer(elevation).unwrap();
+		planet.add_layer(Rivers::new(Parameters { depth: 4, ..Parameters::default() })).unwrap();
+		planet.generate().unwrap();
+
+		let rivers = planet.layer::<Rivers>(Rivers::NAME).unwrap();
+		let elevation = planet.layer::<Elevation>(Elevation::NAME).unwrap();
+		let mesh = rivers.mesh();
+		assert!(!rivers.rivers().is_empty());
+
+		for v in 0..mesh.vertices().len() as u32 {
+			// Every land vertex drains into the sea.
+			let mut w = v;
+			let mut steps = 0;
+			while let Some(r) = rivers.receiver(w) {
+				assert!(rivers.accumulation(r) > rivers.accumulation(w));
+				assert_eq!(rivers.basin(mesh.vertex(w)), rivers.basin(mesh.vertex(v)));
+				w = r;
+				steps += 1;
+				assert!(steps <= mesh.vertices().len())
+			}
+		}
+
+		for river in rivers.rivers() {
+			assert!(river.order >= 1 && river.points.len() >= 2 && river.width > 0.0);
+
+			// The river bed is carved.
+			let carving = rivers.carving().unwrap();
+			let bed = (river.points[0] + river.points[1]).normalize();
+			assert!(carving.sample(bed) <= -rivers.parameters().carve_depth * river.order as f32 + 1e-3)
+		}
+
+		// The elevation includes the carving, and regenerating does not carve twice.
+		let bed = (rivers.rivers()[0].points[0] + rivers.rivers()[0].points[1]).normalize();
+		let carved = elevation.sample(bed);
+		planet.generate().unwrap();
+		assert_eq!(planet.layer::<Elevation>(Elevation::NAME).unwrap().sample(bed), carved);
+
+		let mut plain = Planet::from_seed(4, Descriptor::default());
+		plain.add_layer(Elevation::default()).unwrap();
+		plain.generate().unwrap();
+		let carving = planet.layer::<Rivers>(Rivers::NAME).unwrap().carving().unwrap().sample(bed);
+		assert!((plain.layer::<Elevation>(Elevation::NAME).unwrap().sample(bed) + carving - carved).abs() < 1e-3)
+	}
+}
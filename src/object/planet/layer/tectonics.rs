@@ -0,0 +1,380 @@
+use glam::Vec3;
+use crate::noise::{Noise, Fractal, Octaves};
+use crate::random::Rng;
+use crate::topology::{Cell, bounds::angle};
+use super::{Layer, Context, Field};
+
+/// Crust type of a plate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+	Oceanic,
+	Continental
+}
+
+/// Relative motion of two plates along their boundary.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Boundary {
+	/// The plates move toward each other.
+	Convergent,
+
+	/// The plates move away from each other.
+	Divergent,
+
+	/// The plates slide along each other.
+	Transform
+}
+
+/// Tectonic plate.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Plate {
+	/// Direction of the plate center (unit vector).
+	pub center: Vec3,
+
+	pub kind: Kind,
+
+	/// Euler pole of the plate rotation (unit vector).
+	pub pole: Vec3,
+
+	/// Angular speed around the Euler pole, relative to the maximum speed.
+	pub rate: f32
+}
+
+impl Plate {
+	/// Angular velocity vector of the plate.
+	pub fn angular_velocity(&self) -> Vec3 {
+		self.pole * self.rate
+	}
+
+	/// Surface velocity of the plate at the given point.
+	pub fn velocity(&self, dir: Vec3) -> Vec3 {
+		self.angular_velocity().cross(dir)
+	}
+}
+
+/// Tectonics layer parameters.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Parameters {
+	/// Number of plates.
+	pub count: u32,
+
+	/// Angular width of the boundary features, in radians.
+	pub width: f32,
+
+	/// Distortion of the plates boundaries, in radians.
+	pub roughness: f32,
+
+	/// Mean altitude of continental plates, in m.
+	pub continental_altitude: f32,
+
+	/// Mean altitude of oceanic plates, in m.
+	pub oceanic_altitude: f32,
+
+	/// Maximum height of mountain ranges, in m.
+	pub mountain_height: f32,
+
+	/// Maximum depth of subduction trenches, in m.
+	pub trench_depth: f32,
+
+	/// Maximum depth of rifts, in m.
+	pub rift_depth: f32
+}
+
+impl Default for Parameters {
+	fn default() -> Parameters {
+		Parameters {
+			count: 12,
+			width: 0.06,
+			roughness: 0.1,
+			continental_altitude: 500.0,
+			oceanic_altitude: -3500.0,
+			mountain_height: 5000.0,
+			trench_depth: 4000.0,
+			rift_depth: 2000.0
+		}
+	}
+}
+
+/// Plates partitioning the sphere.
+///
+/// Plates are the cells of a spherical Voronoi diagram of their centers,
+/// with boundaries distorted by noise.
+#[derive(Clone)]
+pub struct Plates {
+	parameters: Parameters,
+	plates: Vec<Plate>,
+	warp: Noise
+}
+
+impl Plates {
+	/// Generate plates from the given random stream.
+	///
+	/// The proportion of oceanic plates follows the ocean fraction of the planet.
+	pub fn new(rng: &mut Rng, parameters: Parameters, ocean_fraction: f32) -> Plates {
+		let count = parameters.count.max(2) as usize;
+		let continental = ((count as f32 * (1.0 - ocean_fraction)).round().max(0.0) as usize).min(count);
+
+		let plates = (0..count).map(|i| {
+			Plate {
+				center: rng.unit_vector(),
+				kind: if i < continental { Kind::Continental } else { Kind::Oceanic },
+				pole: rng.unit_vector(),
+				rate: rng.range(0.2, 1.0)
+			}
+		}).collect();
+
+		let warp = Noise::new(rng, Fractal::Fbm, Octaves {
+			count: 4,
+			frequency: 3.0,
+			..Octaves::default()
+		});
+
+		Plates {
+			parameters,
+			plates,
+			warp
+		}
+	}
+
+	pub fn parameters(&self) -> &Parameters {
+		&self.parameters
+	}
+
+	pub fn plates(&self) -> &[Plate] {
+		&self.plates
+	}
+
+	fn warped(&self, dir: Vec3) -> Vec3 {
+		let dir = dir.normalize();
+		let offset = Vec3::new(
+			self.warp.sample(dir),
+			self.warp.sample(dir + Vec3::new(5.2, 1.3, 7.1)),
+			self.warp.sample(dir + Vec3::new(-3.7, 9.2, -1.9))
+		);
+
+		(dir + offset * self.parameters.roughness).normalize()
+	}
+
+	/// Nearest plate and second nearest plate in the given direction,
+	/// with the angular distance to their boundary.
+	pub fn nearest(&self, dir: Vec3) -> (usize, usize, f32) {
+		let p = self.warped(dir);
+		let mut first = (0, f32::INFINITY);
+		let mut second = (0, f32::INFINITY);
+		for (i, plate) in self.plates.iter().enumerate() {
+			let a = angle(plate.center, p);
+			if a < first.1 {
+				second = first;
+				first = (i, a)
+			} else if a < second.1 {
+				second = (i, a)
+			}
+		}
+
+		(first.0, second.0, (second.1 - first.1) / 2.0)
+	}
+
+	/// Plate in the given direction.
+	pub fn plate(&self, dir: Vec3) -> usize {
+		self.nearest(dir).0
+	}
+
+	/// Plate of the given cell (containing its center).
+	pub fn plate_of(&self, cell: Cell) -> usize {
+		self.plate(cell.center())
+	}
+
+	/// Convergence rate of plate `a` toward plate `b` at the given point (negative if diverging),
+	/// and sliding rate along their boundary.
+	fn motion(&self, a: usize, b: usize, dir: Vec3) -> (f32, f32) {
+		let p = dir.normalize();
+		let (pa, pb) = (&self.plates[a], &self.plates[b]);
+		let normal = pb.center - pa.center;
+		let normal = (normal - p * normal.dot(p)).normalize();
+		let v = pa.velocity(p) - pb.velocity(p);
+		let convergence = v.dot(normal);
+		let sliding = (v - normal * convergence).length();
+		(convergence / 2.0, sliding / 2.0)
+	}
+
+	/// Classify the boundary between the plates `a` and `b` at the given point.
+	pub fn boundary(&self, a: usize, b: usize, dir: Vec3) -> Boundary {
+		let (convergence, sliding) = self.motion(a, b, dir);
+		if convergence.abs() < 0.5 * sliding {
+			Boundary::Transform
+		} else if convergence > 0.0 {
+			Boundary::Convergent
+		} else {
+			Boundary::Divergent
+		}
+	}
+
+	fn altitude(&self, kind: Kind) -> f32 {
+		match kind {
+			Kind::Continental => self.parameters.continental_altitude,
+			Kind::Oceanic => self.parameters.oceanic_altitude
+		}
+	}
+
+	/// Altitude raised by the plates (crust type and collisions), in m.
+	pub fn uplift(&self, dir: Vec3) -> f32 {
+		let (a, b, d) = self.nearest(dir);
+		let w = self.parameters.width;
+		let t = d / w;
+
+		// Crust altitude, blended toward the neighbour plate near the boundary.
+		let own = self.altitude(self.plates[a].kind);
+		let mean = (own + self.altitude(self.plates[b].kind)) / 2.0;
+		let s = t.min(1.0);
+		let base = mean + (own - mean) * s * s * (3.0 - 2.0 * s);
+
+		// Features are scaled by the convergence rate (and not the boundary classification)
+		// to remain continuous along the boundaries.
+		let (convergence, _) = self.motion(a, b, dir);
+		if convergence <= 0.0 {
+			return base
+		}
+
+		let collision = match (self.plates[a].kind, self.plates[b].kind) {
+			(Kind::Continental, Kind::Continental) | (Kind::Oceanic, Kind::Oceanic) => {
+				self.parameters.mountain_height * libm::expf(-t * t)
+			},
+			// Mountains inland of the subduction.
+			(Kind::Continental, Kind::Oceanic) => {
+				self.parameters.mountain_height * t * libm::expf(1.0 - t)
+			},
+			// Subduction trench.
+			(Kind::Oceanic, Kind::Continental) => {
+				-self.parameters.trench_depth * 2.0 * std::f32::consts::E * t * libm::expf(-2.0 * t)
+			}
+		};
+
+		base + collision * convergence.min(1.0)
+	}
+
+	/// Depth carved by diverging plates, in m.
+	pub fn rift(&self, dir: Vec3) -> f32 {
+		let (a, b, d) = self.nearest(dir);
+		let (convergence, _) = self.motion(a, b, dir);
+		if convergence >= 0.0 {
+			return 0.0
+		}
+
+		let t = d / self.parameters.width;
+		self.parameters.rift_depth * (-convergence).min(1.0) * libm::expf(-t * t)
+	}
+}
+
+impl Field<f32> for Plates {
+	/// Altitude displacement: uplift minus rift.
+	fn sample(&self, dir: Vec3) -> f32 {
+		self.uplift(dir) - self.rift(dir)
+	}
+
+	/// Crust altitudes, raised by the mountains or lowered by the trenches and rifts.
+	fn bounds(&self) -> Option<(f32, f32)> {
+		let p = &self.parameters;
+		let (low, high) = (p.continental_altitude.min(p.oceanic_altitude), p.continental_altitude.max(p.oceanic_altitude));
+		Some((low - p.trench_depth.max(0.0) - p.rift_depth.max(0.0), high + p.mountain_height.max(0.0)))
+	}
+}
+
+/// Tectonics layer.
+///
+/// Partitions the planet into plates, whose motion
+/// shapes the relief through the uplift and rift fields.
+pub struct Tectonics {
+	parameters: Parameters,
+	plates: Option<Plates>
+}
+
+impl Tectonics {
+	pub const NAME: &'static str = "tectonics";
+
+	pub fn new(parameters: Parameters) -> Tectonics {
+		Tectonics {
+			parameters,
+			plates: None
+		}
+	}
+
+	/// Generated plates.
+	///
+	/// Panics if the layer has not been evaluated.
+	pub fn plates(&self) -> &Plates {
+		self.plates.as_ref().expect("tectonics layer not evaluated")
+	}
+}
+
+impl Default for Tectonics {
+	fn default() -> Tectonics {
+		Tectonics::new(Parameters::default())
+	}
+}
+
+impl Layer for Tectonics {
+	fn name(&self) -> &str {
+		Tectonics::NAME
+	}
+
+	fn evaluate(&mut self, context: &mut Context) {
+		self.plates = Some(Plates::new(&mut context.rng(), self.parameters, context.descriptor.ocean_fraction))
+	}
+
+	fn displacement(&self) -> Option<Box<dyn Field<f32>>> {
+		self.plates.clone().map(|p| Box::new(p) as Box<dyn Field<f32>>)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::object::planet::{Planet, Descriptor};
+	use crate::object::planet::layer::{Elevation, elevation, Field};
+	use crate::random::Rng;
+	use crate::topology::Cell;
+	use super::{Tectonics, Plates, Parameters, Boundary};
+
+	#[test]
+	fn plates() {
+		let plates = Plates::new(&mut Rng::new(3), Parameters::default(), 0.6);
+		let mut used = vec![false; plates.plates().len()];
+		let mut boundaries = [0; 3];
+		for cell in Cell::all(3) {
+			let dir = cell.center();
+			let (a, b, d) = plates.nearest(dir);
+			used[a] = true;
+			assert!(a != b && d >= 0.0);
+
+			match plates.boundary(a, b, dir) {
+				Boundary::Convergent => boundaries[0] += 1,
+				Boundary::Divergent => boundaries[1] += 1,
+				Boundary::Transform => boundaries[2] += 1
+			}
+
+			let p = plates.parameters();
+			assert!(plates.uplift(dir) <= p.continental_altitude + p.mountain_height);
+			assert!(plates.rift(dir) >= 0.0)
+		}
+
+		assert!(used.iter().all(|u| *u));
+		assert!(boundaries.iter().all(|b| *b > 0))
+	}
+
+	#[test]
+	fn elevation_source() {
+		let mut planet = Planet::from_seed(5, Descriptor::default());
+		let mut e = Elevation::new(elevation::Parameters {
+			amplitude: 0.0,
+			..elevation::Parameters::default()
+		});
+		e.add_source(Tectonics::NAME);
+		planet.add_layer(e).unwrap();
+		planet.add_layer(Tectonics::default()).unwrap();
+		planet.generate().unwrap();
+
+		let plates = planet.layer::<Tectonics>(Tectonics::NAME).unwrap().plates();
+		let e = planet.layer::<Elevation>(Elevation::NAME).unwrap();
+		for cell in Cell::all(1) {
+			assert!((e.sample_cell(cell) - plates.sample_cell(cell)).abs() < 1e-2)
+		}
+	}
+}
@@ -15,4 +15,10 @@ impl Topology {
 	pub fn geometry(&self) -> crate::topology::Sphere {
 		crate::topology::Sphere::new()
 	}
-}
\ No newline at end of file
+}
+
+impl Default for Topology {
+	fn default() -> Topology {
+		Topology::new()
+	}
+}
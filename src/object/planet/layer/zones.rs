@@ -0,0 +1,355 @@
+use std::collections::HashMap;
+use glam::Vec3;
+use crate::location::{Vector3d, Position, Polygon};
+use crate::topology::Cell;
+use super::{Layer, Context, Field, Elevation, Tectonics, Climate, tectonics};
+
+/// Geological zone.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Zone {
+	DeepOcean,
+	ContinentalShelf,
+	Sea,
+	Lake,
+	Plain,
+	Mountain,
+	Plateau,
+	Desert
+}
+
+impl Zone {
+	pub fn is_water(&self) -> bool {
+		matches!(self, Zone::DeepOcean | Zone::ContinentalShelf | Zone::Sea | Zone::Lake)
+	}
+}
+
+/// Zones layer parameters.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Parameters {
+	/// Depth of the classified cells.
+	pub depth: u32,
+
+	/// Altitude (relative to the sea level) under which the continental shelf ends, in m.
+	pub shelf_depth: f32,
+
+	/// Minimum area of an ocean, relative to the planet area.
+	pub ocean_area: f32,
+
+	/// Minimum area of a sea, relative to the planet area.
+	pub sea_area: f32,
+
+	/// Altitude above which the land is mountains, in m.
+	pub mountain_altitude: f32,
+
+	/// Altitude above which the land is a plateau (or mountains if too steep), in m.
+	pub plateau_altitude: f32,
+
+	/// Maximum altitude difference between neighbor cells of a plateau, in m.
+	pub plateau_relief: f32,
+
+	/// Annual precipitation under which the land is a desert, in mm.
+	pub desert_precipitation: f32
+}
+
+impl Default for Parameters {
+	fn default() -> Parameters {
+		Parameters {
+			depth: 5,
+			shelf_depth: -200.0,
+			ocean_area: 0.02,
+			sea_area: 0.0005,
+			mountain_altitude: 2000.0,
+			plateau_altitude: 800.0,
+			plateau_relief: 300.0,
+			desert_precipitation: 250.0
+		}
+	}
+}
+
+/// Connected set of cells of the same zone.
+#[derive(Clone, Debug)]
+pub struct Component {
+	pub zone: Zone,
+
+	pub cells: Vec<Cell>,
+
+	/// Area of the component, in m².
+	pub area: f32,
+
+	/// Closed outlines of the component.
+	///
+	/// Outer boundaries are counterclockwise and holes clockwise.
+	pub outline: Vec<Polygon<f32>>
+}
+
+/// Geological zones layer.
+///
+/// Classifies the cells of a given depth from the elevation, plates and climate layers.
+pub struct Zones {
+	parameters: Parameters,
+
+	/// Planet radius.
+	radius: f32,
+
+	/// Zone of each cell.
+	zones: Vec<Zone>,
+
+	/// Neighbors of each cell.
+	neighbors: Vec<[u32; 3]>
+}
+
+impl Zones {
+	pub const NAME: &'static str = "zones";
+
+	pub fn new(parameters: Parameters) -> Zones {
+		Zones {
+			parameters,
+			radius: 1.0,
+			zones: Vec::new(),
+			neighbors: Vec::new()
+		}
+	}
+
+	pub fn parameters(&self) -> &Parameters {
+		&self.parameters
+	}
+
+	pub fn depth(&self) -> u32 {
+		self.parameters.depth
+	}
+
+	/// Zone of the given cell.
+	///
+	/// Cells coarser than the layer depth take the zone of their center.
+	pub fn zone(&self, cell: Cell) -> Zone {
+		let depth = self.parameters.depth;
+		let cell = if cell.depth() >= depth {
+			cell.ancestor(depth)
+		} else {
+			Cell::locate(cell.center(), depth)
+		};
+
+		self.zones[cell.index() as usize]
+	}
+
+	/// Connected components of every zone.
+	pub fn components(&self) -> Vec<Component> {
+		let mut visited = vec![false; self.zones.len()];
+		let mut components = Vec::new();
+		for i in 0..self.zones.len() {
+			if !visited[i] {
+				components.push(self.component_from(i, &mut visited))
+			}
+		}
+
+		components
+	}
+
+	/// Connected components of the given zone.
+	pub fn components_of(&self, zone: Zone) -> Vec<Component> {
+		self.components().into_iter().filter(|c| c.zone == zone).collect()
+	}
+
+	/// Connected component containing the given direction.
+	pub fn component_at(&self, dir: Vec3) -> Component {
+		let mut visited = vec![false; self.zones.len()];
+		self.component_from(Cell::locate(dir, self.parameters.depth).index() as usize, &mut visited)
+	}
+
+	/// Flood fill the cells of the same zone as the `start` cell.
+	fn flood<F>(&self, start: usize, visited: &mut [bool], same: F) -> Vec<usize> where F: Fn(usize) -> bool {
+		let mut cells = vec![start];
+		visited[start] = true;
+		let mut next = 0;
+		while next < cells.len() {
+			for n in self.neighbors[cells[next]].iter() {
+				let n = *n as usize;
+				if !visited[n] && same(n) {
+					visited[n] = true;
+					cells.push(n)
+				}
+			}
+
+			next += 1
+		}
+
+		cells
+	}
+
+	fn component_from(&self, start: usize, visited: &mut [bool]) -> Component {
+		let depth = self.parameters.depth;
+		let zone = self.zones[start];
+		let indexes = self.flood(start, visited, |n| self.zones[n] == zone);
+
+		let cells: Vec<Cell> = indexes.iter().map(|i| Cell::new(depth, *i as u32)).collect();
+		let area = cells.iter().map(|c| c.area()).sum::<f32>() * self.radius * self.radius;
+
+		Component {
+			zone,
+			outline: self.outline(&cells, |n| self.zones[n] == zone),
+			cells,
+			area
+		}
+	}
+
+	/// Outlines of a set of cells, chaining the edges shared with cells outside of the set.
+	///
+	/// Shared corners of neighbor cells are computed identically,
+	/// so edges are chained by exact comparison.
+	fn outline<F>(&self, cells: &[Cell], inside: F) -> Vec<Polygon<f32>> where F: Fn(usize) -> bool {
+		fn key(v: Vec3) -> [u32; 3] {
+			[v.x.to_bits(), v.y.to_bits(), v.z.to_bits()]
+		}
+
+		let mut edges = Vec::new();
+		let mut starting: HashMap<[u32; 3], Vec<usize>> = HashMap::new();
+		for cell in cells {
+			let corners = cell.corners();
+			for (i, n) in self.neighbors[cell.index() as usize].iter().enumerate() {
+				if !inside(*n as usize) {
+					let (a, b) = (corners[(i + 1) % 3], corners[(i + 2) % 3]);
+					starting.entry(key(a)).or_default().push(edges.len());
+					edges.push((a, b))
+				}
+			}
+		}
+
+		let mut used = vec![false; edges.len()];
+		let mut outlines = Vec::new();
+		for first in 0..edges.len() {
+			if used[first] {
+				continue
+			}
+
+			let mut vertices = Vec::new();
+			let mut e = first;
+			loop {
+				used[e] = true;
+				let (a, b) = edges[e];
+				vertices.push(Position::from(Vector3d::from(a)));
+				match starting.get(&key(b)).and_then(|next| next.iter().find(|n| !used[**n])) {
+					Some(n) => e = *n,
+					None => break
+				}
+			}
+
+			outlines.push(Polygon::new(vertices))
+		}
+
+		outlines
+	}
+}
+
+impl Default for Zones {
+	fn default() -> Zones {
+		Zones::new(Parameters::default())
+	}
+}
+
+impl Layer for Zones {
+	fn name(&self) -> &str {
+		Zones::NAME
+	}
+
+	fn inputs(&self) -> Vec<String> {
+		vec![Elevation::NAME.to_string(), Tectonics::NAME.to_string(), Climate::NAME.to_string()]
+	}
+
+	fn evaluate(&mut self, context: &mut Context) {
+		let p = self.parameters;
+		let elevation = context.layer::<Elevation>(Elevation::NAME).unwrap();
+		let plates = context.layer::<Tectonics>(Tectonics::NAME).unwrap().plates();
+		let climate = context.layer::<Climate>(Climate::NAME).unwrap();
+
+		self.radius = context.descriptor.radius;
+		let sea_level = context.descriptor.sea_level;
+		self.neighbors = Cell::all(p.depth).map(|c| {
+			let [a, b, c] = c.neighbors();
+			[a.index(), b.index(), c.index()]
+		}).collect();
+
+		let altitudes: Vec<f32> = Cell::all(p.depth).map(|c| elevation.sample_cell(c) - sea_level).collect();
+
+		// Land, water being classified afterward.
+		self.zones = Cell::all(p.depth).map(|cell| {
+			let i = cell.index() as usize;
+			let altitude = altitudes[i];
+			let relief = self.neighbors[i].iter().map(|n| (altitudes[*n as usize] - altitude).abs()).fold(0.0f32, f32::max);
+
+			if altitude < 0.0 {
+				Zone::Lake
+			} else if altitude >= p.mountain_altitude || (altitude >= p.plateau_altitude && relief > p.plateau_relief) {
+				Zone::Mountain
+			} else if altitude >= p.plateau_altitude {
+				Zone::Plateau
+			} else if climate.precipitation(cell) < p.desert_precipitation {
+				Zone::Desert
+			} else {
+				Zone::Plain
+			}
+		}).collect();
+
+		// Water bodies, by size.
+		let total = 4.0 * std::f32::consts::PI;
+		let mut visited = vec![false; self.zones.len()];
+		for i in 0..self.zones.len() {
+			if visited[i] || !self.zones[i].is_water() {
+				continue
+			}
+
+			let body = self.flood(i, &mut visited, |n| self.zones[n].is_water());
+			let area = body.iter().map(|c| Cell::new(p.depth, *c as u32).area()).sum::<f32>() / total;
+			for c in body {
+				self.zones[c] = if area >= p.ocean_area {
+					let continental = plates.plates()[plates.plate_of(Cell::new(p.depth, c as u32))].kind == tectonics::Kind::Continental;
+					if altitudes[c] >= p.shelf_depth || (continental && altitudes[c] >= 2.0 * p.shelf_depth) {
+						Zone::ContinentalShelf
+					} else {
+						Zone::DeepOcean
+					}
+				} else if area >= p.sea_area {
+					Zone::Sea
+				} else {
+					Zone::Lake
+				}
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::object::planet::{Planet, Descriptor};
+	use crate::object::planet::layer::{Elevation, Tectonics, Climate};
+	use super::{Zones, Parameters};
+
+	#[test]
+	fn components() {
+		let mut planet = Planet::from_seed(9, Descriptor::default());
+		let mut elevation = Elevation::default();
+		elevation.add_source(Tectonics::NAME);
+		planet.add_layer(elevation).unwrap();
+		planet.add_layer(Tectonics::default()).unwrap();
+		planet.add_layer(Climate::default()).unwrap();
+		planet.add_layer(Zones::new(Parameters { depth: 4, ..Parameters::default() })).unwrap();
+		planet.generate().unwrap();
+
+		let zones = planet.layer::<Zones>(Zones::NAME).unwrap();
+		let components = zones.components();
+		let area: f32 = components.iter().map(|c| c.area).sum();
+		let expected = 4.0 * std::f32::consts::PI * planet.descriptor().radius.powi(2);
+		assert!((area - expected).abs() / expected < 1e-3);
+
+		for c in &components {
+			assert!(c.cells.iter().all(|cell| zones.zone(*cell) == c.zone));
+			assert!(!c.outline.is_empty() || components.len() == 1);
+
+			// The outlines (counterclockwise, holes clockwise) enclose the component area, modulo the sphere area.
+			let sphere = 4.0 * std::f32::consts::PI;
+			let enclosed = c.outline.iter().map(|o| o.area()).sum::<f32>();
+			let area = c.area / planet.descriptor().radius.powi(2);
+			let diff = (enclosed - area).rem_euclid(sphere);
+			assert!(diff.min(sphere - diff) < 1e-3)
+		}
+	}
+}
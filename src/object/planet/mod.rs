@@ -1,14 +1,539 @@
 pub mod layer;
+pub mod atmosphere;
+
+use glam::Vec3;
+use layer::{Layer, Field};
+use crate::random::Rng;
+use crate::location::Frame;
+use crate::topology::Cell;
+use super::body::{Body, Composition, G};
+use super::surface::{Surface, Analytic, Triangles, Hit, raycast_cells};
+pub use atmosphere::{Atmosphere, Gases};
+
+/// Global parameters of a planet.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Descriptor {
+	/// Seed from which every layer is generated.
+	pub seed: u64,
+
+	/// Mean radius of the planet, in m.
+	pub radius: f32,
+
+	/// Mass of the planet, in kg.
+	pub mass: f64,
+
+	pub composition: Composition,
+
+	/// Sidereal rotation period, in s.
+	pub rotation_period: f32,
+
+	/// Angle between the rotation axis and the orbit normal, in radians.
+	pub axial_tilt: f32,
+
+	/// Flattening of the poles, `1 - polar radius / equatorial radius`.
+	pub oblateness: f32,
+
+	pub atmosphere: Atmosphere,
+
+	/// Proportion of the surface covered by oceans.
+	pub ocean_fraction: f32,
+
+	/// Mean surface temperature at sea level, in °C.
+	pub mean_temperature: f32,
+
+	/// Temperature decrease with altitude, in °C/m.
+	pub lapse_rate: f32,
+
+	/// Mean annual precipitation, in mm.
+	pub precipitation: f32,
+
+	/// Altitude of the sea level, in m.
+	pub sea_level: f32
+}
+
+impl Default for Descriptor {
+	/// Earth-like planet.
+	fn default() -> Descriptor {
+		Descriptor {
+			seed: 0,
+			radius: 6_371_000.0,
+			mass: 5.972e24,
+			composition: Composition::rocky(),
+			rotation_period: 86_164.1,
+			axial_tilt: 0.409,
+			oblateness: 0.003_353,
+			atmosphere: Atmosphere::default(),
+			ocean_fraction: 0.71,
+			mean_temperature: 15.0,
+			lapse_rate: 0.0065,
+			precipitation: 1000.0,
+			sea_level: 0.0
+		}
+	}
+}
+
+impl Descriptor {
+	/// Standard gravitational parameter, in m³/s².
+	pub fn mu(&self) -> f64 {
+		G * self.mass
+	}
+
+	/// Equatorial radius, in m (the oblate spheroid having the volume of the mean radius sphere).
+	pub fn equatorial_radius(&self) -> f32 {
+		self.radius / libm::cbrtf(1.0 - self.oblateness)
+	}
+
+	/// Polar radius, in m.
+	pub fn polar_radius(&self) -> f32 {
+		self.equatorial_radius() * (1.0 - self.oblateness)
+	}
+
+	/// Radius of the spheroid (the surface at zero altitude) in the given direction, the rotation axis being `z`, in m.
+	pub fn spheroid_radius(&self, dir: Vec3) -> f32 {
+		let a = self.equatorial_radius();
+		let e = 1.0 / ((1.0 - self.oblateness) * (1.0 - self.oblateness)) - 1.0;
+		a / (1.0 + e * dir.z * dir.z / dir.length_squared()).sqrt()
+	}
+
+	/// Rotation speed, in rad/s.
+	pub fn angular_velocity(&self) -> f32 {
+		2.0 * std::f32::consts::PI / self.rotation_period
+	}
+
+	/// Mean gravitational acceleration on the surface, ignoring the rotation, in m/s².
+	pub fn surface_gravity(&self) -> f32 {
+		(self.mu() / (self.radius as f64 * self.radius as f64)) as f32
+	}
+
+	/// Effective gravity on the surface (including the centrifugal acceleration) at the given latitude (in radians), in m/s².
+	///
+	/// Uses Clairaut's theorem, valid for small oblateness and rotation speed.
+	pub fn surface_gravity_at(&self, latitude: f32) -> f32 {
+		let a = self.equatorial_radius();
+		let w = self.angular_velocity();
+		let attraction = (self.mu() / (a as f64 * a as f64)) as f32;
+
+		// Ratio of the centrifugal acceleration to the gravity at the equator.
+		let m = w * w * a / attraction;
+		let equator = attraction * (1.0 + self.oblateness - 1.5 * m);
+		let s = latitude.sin();
+		equator * (1.0 + (2.5 * m - self.oblateness) * s * s)
+	}
+
+	/// Second zonal harmonic of the gravity field, from the oblateness and the rotation (Clairaut's theorem).
+	pub fn j2(&self) -> f32 {
+		let a = self.equatorial_radius() as f64;
+		let w = self.angular_velocity() as f64;
+		let m = (w * w * a * a * a / self.mu()) as f32;
+		(2.0 * self.oblateness - m) / 3.0
+	}
+
+	/// Velocity needed to escape the planet gravity from its surface, in m/s.
+	pub fn escape_velocity(&self) -> f32 {
+		(2.0 * self.mu() / self.radius as f64).sqrt() as f32
+	}
+
+	/// Whether the planet has surface liquid water.
+	pub fn has_oceans(&self) -> bool {
+		self.ocean_fraction > 0.0
+	}
+}
 
 pub struct Planet {
+	/// Global infos about the planet.
+	d: Descriptor,
+
 	/// General topology of the planet.
-	topology: layer::Topology
+	topology: layer::Topology,
+
+	/// Other layers.
+	layers: layer::Registry
 }
 
 impl Planet {
-	pub fn new() -> Planet {
+	/// Create a new planet.
+	///
+	/// Its generation is fully determined by the descriptor and the registered layers.
+	pub fn new(d: Descriptor) -> Planet {
 		Planet {
-			topology: layer::Topology::new()
+			d,
+			topology: layer::Topology::new(),
+			layers: layer::Registry::new()
 		}
 	}
-}
\ No newline at end of file
+
+	/// Create a new planet, replacing the seed of the descriptor.
+	pub fn from_seed(seed: u64, d: Descriptor) -> Planet {
+		Planet::new(Descriptor { seed, ..d })
+	}
+
+	pub fn seed(&self) -> u64 {
+		self.d.seed
+	}
+
+	pub fn descriptor(&self) -> &Descriptor {
+		&self.d
+	}
+
+	/// Root of the random streams of the planet.
+	pub fn rng(&self) -> Rng {
+		Rng::new(self.d.seed)
+	}
+
+	pub fn topology(&self) -> &layer::Topology {
+		&self.topology
+	}
+
+	/// Register a new layer.
+	///
+	/// It will be evaluated on the next call to [`generate`](Planet::generate).
+	pub fn add_layer<L: Layer>(&mut self, layer: L) -> Result<(), layer::Error> {
+		self.layers.add(layer)
+	}
+
+	pub fn layer<L: Layer>(&self, name: &str) -> Option<&L> {
+		self.layers.get(name)
+	}
+
+	pub fn layers(&self) -> &layer::Registry {
+		&self.layers
+	}
+
+	/// Evaluate every layer, in dependency order.
+	pub fn generate(&mut self) -> Result<(), layer::Error> {
+		let rng = self.rng();
+		self.layers.evaluate(rng, &self.d, &self.topology)
+	}
+
+	/// Refine every layer in the given cell.
+	///
+	/// Cells which do not contain each other can be refined in any order,
+	/// and refining a cell again gives the same result.
+	pub fn refine(&mut self, cell: crate::topology::Cell) -> Result<(), layer::Error> {
+		let rng = self.rng();
+		self.layers.refine(rng, &self.d, &self.topology, cell)
+	}
+
+	/// Altitude of the surface in the given direction, relative to the spheroid, in m.
+	///
+	/// It is zero until the elevation layer is generated.
+	pub fn altitude(&self, dir: Vec3) -> f32 {
+		match self.layer::<layer::Elevation>(layer::Elevation::NAME) {
+			Some(elevation) if elevation.is_evaluated() => elevation.sample(dir),
+			_ => 0.0
+		}
+	}
+
+	/// Lowest and highest altitudes of the surface, relative to the spheroid, in m.
+	///
+	/// They are the bounds of the elevation layer (see [`Field::bounds`]), zero until it is generated.
+	/// A displacement with unknown bounds is assumed to stay within 1% of the mean radius.
+	pub fn relief(&self) -> (f32, f32) {
+		match self.layer::<layer::Elevation>(layer::Elevation::NAME) {
+			Some(elevation) if elevation.is_evaluated() => {
+				elevation.bounds().unwrap_or((-self.d.radius * 0.01, self.d.radius * 0.01))
+			},
+			_ => (0.0, 0.0)
+		}
+	}
+
+	/// Checks if the surface in the given direction is below the sea level.
+	pub fn is_ocean(&self, dir: Vec3) -> bool {
+		self.altitude(dir) < self.d.sea_level
+	}
+}
+
+impl Body for Planet {
+	fn mass(&self) -> f64 {
+		self.d.mass
+	}
+
+	fn composition(&self) -> Composition {
+		self.d.composition
+	}
+
+	/// Spheroid radius displaced by the elevation layer, if generated.
+	fn surface_radius(&self, dir: Vec3) -> f32 {
+		self.d.spheroid_radius(dir) + self.altitude(dir)
+	}
+
+	/// Equatorial radius raised by the highest [`relief`](Planet::relief).
+	fn bounding_radius(&self) -> f32 {
+		self.d.equatorial_radius() + self.relief().1.max(0.0)
+	}
+
+	/// Point mass attraction with the oblateness (`J2`) term, the rotation axis being `z`.
+	fn gravity(&self, p: Vec3) -> Vec3 {
+		let r2 = p.length_squared();
+		let r = r2.sqrt();
+		let a = self.d.equatorial_radius();
+		let k = 1.5 * self.d.j2() * (a * a) / r2;
+		let z2 = p.z * p.z / r2;
+		let mu = (self.d.mu() / (r2 as f64 * r as f64)) as f32;
+
+		let horizontal = 1.0 - k * (5.0 * z2 - 1.0);
+		let vertical = 1.0 - k * (5.0 * z2 - 3.0);
+		-Vec3::new(p.x * horizontal, p.y * horizontal, p.z * vertical) * mu
+	}
+}
+
+/// Shape of the surface used by the queries.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Model {
+	/// Flat triangles of the cells at the given depth, with their corners on the surface.
+	Mesh(u32),
+
+	/// Exact surface, following the [`surface_radius`](Body::surface_radius).
+	Elevation
+}
+
+/// Intersection of a ray with the planet surface.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RayHit {
+	pub point: Vec3,
+
+	/// Surface normal, facing the ray origin.
+	pub normal: Vec3,
+
+	/// Cell hit, for the [`Model::Mesh`] model only.
+	pub region: Option<Cell>,
+
+	/// Distance from the ray origin, in m.
+	pub distance: f32
+}
+
+/// Surface queries.
+///
+/// Points are relative to the center of the planet, in its own frame, in m.
+impl Planet {
+	/// Range of the surface radius, from the spheroid and the [`relief`](Planet::relief).
+	pub fn radius_range(&self) -> (f32, f32) {
+		let (low, high) = self.relief();
+		(self.d.polar_radius() + low.min(0.0), self.d.equatorial_radius() + high.max(0.0))
+	}
+
+	/// Local tangent frame (East-North-Up) in the given direction.
+	///
+	/// If `terrain` is true, the frame is aligned with the normal of the exact surface,
+	/// otherwise with the sphere normal.
+	pub fn frame(&self, dir: Vec3, terrain: bool) -> Frame {
+		if terrain {
+			Frame::aligned(dir, Analytic(self).normal(dir))
+		} else {
+			Frame::new(dir)
+		}
+	}
+
+	/// Surface triangle of the given cell.
+	fn triangle(&self, cell: Cell) -> [Vec3; 3] {
+		let [a, b, c] = cell.corners();
+		[a * self.surface_radius(a), b * self.surface_radius(b), c * self.surface_radius(c)]
+	}
+
+	/// Surface of the given model.
+	pub fn surface(&self, model: Model) -> Box<dyn Surface + '_> {
+		match model {
+			Model::Mesh(depth) => Box::new(Triangles::new(move |dir| Some(self.triangle(Cell::locate(dir, depth))), self.bounding_radius())),
+			Model::Elevation => Box::new(Analytic(self))
+		}
+	}
+
+	/// First intersection of the ray with the surface.
+	///
+	/// With [`Model::Mesh`], the cells are descended through their bounding boxes
+	/// and the triangles are tested exactly.
+	/// With [`Model::Elevation`], the ray marches against the exact surface.
+	pub fn raycast(&self, origin: Vec3, dir: Vec3, model: Model) -> Option<RayHit> {
+		let dir = dir.normalize();
+		match model {
+			Model::Mesh(depth) => {
+				let (min, max) = self.radius_range();
+				let leaf = |cell: Cell| if cell.depth() == depth { Some(self.triangle(cell)) } else { None };
+				let (cell, distance) = raycast_cells(origin, dir, min, max, leaf)?;
+				let [a, b, c] = self.triangle(cell);
+				let normal = (b - a).cross(c - a).normalize();
+				Some(RayHit {
+					point: origin + dir * distance,
+					normal: if normal.dot(dir) > 0.0 { -normal } else { normal },
+					region: Some(cell),
+					distance
+				})
+			},
+			Model::Elevation => {
+				let hit = self.surface(model).raycast(origin, dir)?;
+				Some(RayHit {
+					point: hit.point,
+					normal: hit.normal,
+					region: None,
+					distance: hit.distance
+				})
+			}
+		}
+	}
+
+	pub fn closest_point(&self, p: Vec3, model: Model) -> Vec3 {
+		self.surface(model).closest_point(p)
+	}
+
+	/// Signed distance to the surface, positive above.
+	pub fn signed_distance(&self, p: Vec3, model: Model) -> f32 {
+		self.surface(model).signed_distance(p)
+	}
+
+	/// First crossing of the segment `[a, b]` with the surface.
+	pub fn intersect_segment(&self, a: Vec3, b: Vec3, model: Model) -> Option<Hit> {
+		self.surface(model).intersect_segment(a, b)
+	}
+
+	/// First contact of a sphere moving from `a` to `b` with the surface.
+	pub fn sphere_cast(&self, a: Vec3, b: Vec3, radius: f32, model: Model) -> Option<Hit> {
+		self.surface(model).sphere_cast(a, b, radius)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use glam::Vec3;
+	use crate::object::Body;
+	use crate::topology::Cell;
+	use super::{Planet, Model, Descriptor, Atmosphere, Gases};
+	use super::layer::{Elevation, Craters, Tectonics, Climate, Field, elevation, climate};
+
+	#[test]
+	fn descriptor() {
+		let earth = Descriptor::default();
+		assert!((earth.equatorial_radius() - 6_378_100.0).abs() < 1_000.0);
+		assert!((earth.polar_radius() - 6_356_800.0).abs() < 1_000.0);
+		assert!((earth.surface_gravity_at(0.0) - 9.780).abs() < 0.005);
+		assert!((earth.surface_gravity_at(std::f32::consts::FRAC_PI_2) - 9.832).abs() < 0.005);
+		assert!((earth.escape_velocity() - 11_186.0).abs() < 10.0);
+		assert!((earth.j2() - 1.0826e-3).abs() < 2e-6);
+
+		// The oblateness pulls more at the equator.
+		let planet = Planet::new(earth);
+		let (equator, pole) = (Vec3::new(1.0, 0.0, 0.0) * 7e6, Vec3::new(0.0, 0.0, 1.0) * 7e6);
+		let point = (earth.mu() / 49e12) as f32;
+		assert!(planet.gravity(equator).length() > point && planet.gravity(pole).length() < point);
+		assert!(planet.gravity(Vec3::new(1.0, 2.0, 3.0) * 3e6).normalize().dot(-Vec3::new(1.0, 2.0, 3.0).normalize()) > 0.999);
+
+		// The surface follows the same spheroid.
+		assert!((planet.surface_radius(Vec3::new(1.0, 0.0, 0.0)) - earth.equatorial_radius()).abs() < 1.0);
+		assert!((planet.surface_radius(Vec3::new(0.0, 0.0, 1.0)) - earth.polar_radius()).abs() < 1.0);
+		assert_eq!(planet.bounding_radius(), earth.equatorial_radius());
+
+		let air = Gases::earth().molar_mass();
+		assert!((air - 0.028_97).abs() < 1e-4);
+		let atmosphere = Atmosphere::isothermal(101_325.0, Gases::earth(), 288.0, earth.surface_gravity());
+		assert!((atmosphere.scale_height - 8_430.0).abs() < 50.0);
+		assert!((atmosphere.density_at(0.0, 288.0) - 1.225).abs() < 0.01);
+		assert_eq!(Atmosphere::none().pressure_at(0.0), 0.0)
+	}
+
+	#[test]
+	fn queries() {
+		let mut planet = Planet::new(Descriptor { oblateness: 0.0, ..Descriptor::default() });
+		planet.add_layer(Elevation::default()).unwrap();
+		let dir = Vec3::new(1.0, 2.0, 3.0).normalize();
+		assert_eq!(planet.altitude(dir), 0.0);
+		planet.generate().unwrap();
+
+		// The altitude follows the elevation layer, and decides the oceans.
+		let radius = planet.descriptor().radius;
+		assert!((planet.surface_radius(dir) - radius - planet.altitude(dir)).abs() < 1.0);
+		let oceans = Cell::all(3).filter(|c| planet.is_ocean(c.center())).count();
+		assert!(oceans > 0 && oceans < Cell::count(3) as usize);
+		assert!(Cell::all(3).all(|c| planet.is_ocean(c.center()) == (planet.altitude(c.center()) < planet.descriptor().sea_level)));
+		let (above, below) = (dir * (radius + 20_000.0), dir * (radius - 20_000.0));
+
+		// The elevation model follows the relief, not the sphere.
+		let surface = planet.surface_radius(dir);
+		assert!((surface - radius).abs() > 1.0);
+		let hit = planet.intersect_segment(above, below, Model::Elevation).unwrap();
+		assert!((hit.point.length() - surface).abs() < 2.0);
+		assert!((hit.distance - (radius + 20_000.0 - surface)).abs() < 2.0);
+		assert!(planet.signed_distance(hit.point, Model::Elevation).abs() < 2.0);
+		assert!(planet.signed_distance(above, Model::Elevation) > 0.0 && planet.signed_distance(below, Model::Elevation) < 0.0);
+		assert!((planet.closest_point(above, Model::Elevation) - above).length() <= 20_000.0 + radius - surface + 2.0);
+
+		// A sphere stops before the surface.
+		let cast = planet.sphere_cast(above, below, 100.0, Model::Elevation).unwrap();
+		assert!(cast.distance < hit.distance);
+		let center = above - dir * cast.distance;
+		assert!((planet.signed_distance(center, Model::Elevation) - 100.0).abs() < 5.0);
+		assert!(planet.signed_distance(cast.point, Model::Elevation).abs() < 2.0);
+
+		// The mesh passes through the displaced corners of its cells.
+		let corner = Cell::locate(dir, 5).corners()[0];
+		let hit = planet.intersect_segment(corner * (radius + 20_000.0), corner * (radius - 20_000.0), Model::Mesh(5)).unwrap();
+		assert!((hit.point.length() - planet.surface_radius(corner)).abs() < 2.0);
+
+		// Rays find the same surfaces, and the cell hit by the mesh one.
+		let origin = dir * (radius * 2.0);
+		let ray = planet.raycast(origin, -dir, Model::Elevation).unwrap();
+		assert!((ray.point.length() - surface).abs() < 2.0 && ray.region.is_none());
+		assert!(ray.normal.dot(dir) > 0.5);
+		let ray = planet.raycast(origin, -dir, Model::Mesh(5)).unwrap();
+		assert_eq!(ray.region, Some(Cell::locate(dir, 5)));
+		assert!((ray.distance - (origin - ray.point).length()).abs() < 1.0);
+		assert!(planet.raycast(origin, dir, Model::Mesh(5)).is_none() && planet.raycast(origin, dir, Model::Elevation).is_none());
+
+		// The terrain frame tilts with the relief.
+		let (sphere, terrain) = (planet.frame(dir, false), planet.frame(dir, true));
+		assert!((sphere.up - dir).length() < 1e-5);
+		assert!((terrain.up - planet.surface(Model::Elevation).normal(dir)).length() < 1e-5 && terrain.up != sphere.up);
+		assert!(terrain.up.dot(dir) > 0.5);
+		assert!(terrain.east.dot(terrain.up).abs() < 1e-5 && terrain.north.dot(terrain.up).abs() < 1e-5)
+	}
+
+	#[test]
+	fn relief() {
+		// Small moon, whose craters dig deeper than 1% of its radius.
+		let mut moon = Planet::new(Descriptor { radius: 200_000.0, oblateness: 0.0, ..Descriptor::default() });
+		let mut e = Elevation::new(elevation::Parameters { amplitude: 0.0, ..elevation::Parameters::default() });
+		e.add_source(Craters::NAME);
+		moon.add_layer(Craters::default()).unwrap();
+		moon.add_layer(e).unwrap();
+		assert_eq!(moon.radius_range(), (200_000.0, 200_000.0));
+		moon.generate().unwrap();
+
+		let (min, max) = moon.radius_range();
+		assert!(Cell::all(4).all(|c| (min..=max).contains(&moon.surface_radius(c.center()))));
+		assert_eq!(max, moon.bounding_radius());
+
+		// The deepest crater floor is still hit.
+		let craters = moon.layer::<Craters>(Craters::NAME).unwrap().impacts().craters();
+		let floor = craters.iter().map(|c| c.center).min_by(|a, b| moon.surface_radius(*a).total_cmp(&moon.surface_radius(*b))).unwrap();
+		let depth = moon.surface_radius(floor);
+		assert!(depth < 198_000.0 && depth >= min);
+		let hit = moon.raycast(floor * 400_000.0, -floor, Model::Elevation).unwrap();
+		assert!((hit.point.length() - depth).abs() < 1.0);
+		let hit = moon.raycast(floor * 400_000.0, -floor, Model::Mesh(6)).unwrap();
+		assert!(hit.point.length() < 199_000.0)
+	}
+
+	#[test]
+	fn golden() {
+		// Samples pinned across machines: the generation must not depend on the platform math library.
+		let mut planet = Planet::from_seed(42, Descriptor::default());
+		let mut e = Elevation::default();
+		e.add_source(Tectonics::NAME);
+		e.add_source(Craters::NAME);
+		planet.add_layer(Tectonics::default()).unwrap();
+		planet.add_layer(Craters::default()).unwrap();
+		planet.add_layer(e).unwrap();
+		planet.add_layer(Climate::new(climate::Parameters { depth: 3, ..climate::Parameters::default() })).unwrap();
+		planet.generate().unwrap();
+
+		let elevation = planet.layer::<Elevation>(Elevation::NAME).unwrap();
+		let climate = planet.layer::<Climate>(Climate::NAME).unwrap();
+		let expected = [
+			(Vec3::new(1.0, 2.0, 3.0), [0x43ac77d0, 0xc0c8ec9a, 0x44783aec]),
+			(Vec3::new(-0.3, 0.8, -0.1), [0xc4880b7c, 0x41f9d862, 0x43876b92])
+		];
+
+		for (dir, bits) in expected.iter() {
+			let c = climate.sample(*dir);
+			assert_eq!([elevation.sample(*dir).to_bits(), c.temperature.to_bits(), c.precipitation.to_bits()], *bits)
+		}
+	}
+}
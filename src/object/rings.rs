@@ -0,0 +1,182 @@
+use glam::Vec3;
+use super::body::{Body, Composition};
+
+/// Global parameters of a ring system.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Descriptor {
+	/// Mass, in kg.
+	pub mass: f64,
+
+	/// Radius of the inner edge, in m.
+	pub inner_radius: f32,
+
+	/// Radius of the outer edge, in m.
+	pub outer_radius: f32,
+
+	/// Vertical thickness, in m.
+	pub thickness: f32,
+
+	/// Proportion of the light blocked by the rings.
+	pub opacity: f32,
+
+	pub composition: Composition
+}
+
+impl Default for Descriptor {
+	/// Saturn-like rings.
+	fn default() -> Descriptor {
+		Descriptor {
+			mass: 1.54e19,
+			inner_radius: 74_500_000.0,
+			outer_radius: 140_220_000.0,
+			thickness: 20.0,
+			opacity: 0.5,
+			composition: Composition { metal: 0.0, rock: 0.01, ice: 0.99, gas: 0.0 }
+		}
+	}
+}
+
+/// Ring system, in the `xy` plane of its parent body.
+pub struct Rings {
+	d: Descriptor
+}
+
+impl Rings {
+	pub fn new(d: Descriptor) -> Rings {
+		Rings {
+			d
+		}
+	}
+
+	pub fn descriptor(&self) -> &Descriptor {
+		&self.d
+	}
+}
+
+impl Default for Rings {
+	fn default() -> Rings {
+		Rings::new(Descriptor::default())
+	}
+}
+
+impl Body for Rings {
+	fn mass(&self) -> f64 {
+		self.d.mass
+	}
+
+	fn composition(&self) -> Composition {
+		self.d.composition
+	}
+
+	/// Outer radius in the ring plane (within the thickness), 0 elsewhere.
+	fn surface_radius(&self, dir: Vec3) -> f32 {
+		let dir = dir.normalize();
+		if (dir.z * self.d.outer_radius).abs() <= self.d.thickness / 2.0 {
+			self.d.outer_radius
+		} else {
+			0.0
+		}
+	}
+
+	fn bounding_radius(&self) -> f32 {
+		self.d.outer_radius
+	}
+
+	/// Gravity at the outer edge in the ring plane, 0 elsewhere.
+	fn surface_gravity(&self, dir: Vec3) -> f32 {
+		match self.surface_radius(dir) {
+			r if r > 0.0 => self.gravity(dir.normalize() * r).length(),
+			_ => 0.0
+		}
+	}
+}
+
+/// Global parameters of an asteroid belt.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BeltDescriptor {
+	/// Total mass, in kg.
+	pub mass: f64,
+
+	/// Distance of the inner edge from the star, in m.
+	pub inner_radius: f32,
+
+	/// Distance of the outer edge from the star, in m.
+	pub outer_radius: f32,
+
+	/// Angular half-width of the belt above and below the orbital plane, in radians.
+	pub inclination: f32,
+
+	/// Approximate number of asteroids larger than 1 km.
+	pub count: u32,
+
+	pub composition: Composition
+}
+
+impl Default for BeltDescriptor {
+	/// Main asteroid belt of the Solar System.
+	fn default() -> BeltDescriptor {
+		BeltDescriptor {
+			mass: 2.39e21,
+			inner_radius: 3.1e11,
+			outer_radius: 4.9e11,
+			inclination: 0.35,
+			count: 1_000_000,
+			composition: Composition::rocky()
+		}
+	}
+}
+
+/// Asteroid belt around a star.
+///
+/// Its asteroids are not stored: they are generated on demand.
+pub struct Belt {
+	d: BeltDescriptor
+}
+
+impl Belt {
+	pub fn new(d: BeltDescriptor) -> Belt {
+		Belt {
+			d
+		}
+	}
+
+	pub fn descriptor(&self) -> &BeltDescriptor {
+		&self.d
+	}
+
+	/// Checks if the given position (relative to the star) is inside the belt.
+	pub fn contains(&self, p: Vec3) -> bool {
+		let r = p.length();
+		r >= self.d.inner_radius && r <= self.d.outer_radius && (p.z / r).abs().asin() <= self.d.inclination
+	}
+}
+
+impl Default for Belt {
+	fn default() -> Belt {
+		Belt::new(BeltDescriptor::default())
+	}
+}
+
+impl Body for Belt {
+	fn mass(&self) -> f64 {
+		self.d.mass
+	}
+
+	fn composition(&self) -> Composition {
+		self.d.composition
+	}
+
+	/// A belt has no surface.
+	fn surface_radius(&self, _dir: Vec3) -> f32 {
+		0.0
+	}
+
+	fn bounding_radius(&self) -> f32 {
+		self.d.outer_radius
+	}
+
+	/// A belt has no surface.
+	fn surface_gravity(&self, _dir: Vec3) -> f32 {
+		0.0
+	}
+}
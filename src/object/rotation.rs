@@ -0,0 +1,204 @@
+use std::f64::consts::PI;
+use crate::location::{Vector3d, Position};
+
+/// Season of a hemisphere, starting at the equinoxes and solstices.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Season {
+	Spring,
+	Summer,
+	Autumn,
+	Winter
+}
+
+/// Rotation of a body around its (tilted) axis.
+///
+/// The body-fixed frame has its `z` axis along the rotation axis (the north pole),
+/// and its `x` axis toward the prime meridian.
+/// Inertial vectors are expressed in the frame of the orbits (the `xy` reference plane of the parent).
+/// Times are in s, as for [`Orbit`](super::Orbit).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rotation {
+	/// Rotation axis (unit vector).
+	axis: Vector3d<f64>,
+
+	/// Normal of the orbital plane around the star (unit vector).
+	normal: Vector3d<f64>,
+
+	/// Direction of the prime meridian at the epoch, orthogonal to the axis.
+	reference: Vector3d<f64>,
+
+	/// Sidereal rotation period.
+	period: f64,
+
+	/// Orbital period around the star (infinite if the body does not orbit).
+	year: f64
+}
+
+impl Rotation {
+	/// Rotation of the given period, the axis being tilted from the orbit normal by `tilt` (in radians)
+	/// toward the `direction` vector (which must not be colinear with the normal).
+	///
+	/// The prime meridian initially points toward the projection of `direction`.
+	pub fn new(normal: Vector3d<f64>, tilt: f64, direction: Vector3d<f64>, period: f64, year: f64) -> Rotation {
+		let normal = normal.normalize();
+		let toward = (direction - normal * direction.scalar(normal)).normalize();
+		let axis = normal * tilt.cos() + toward * tilt.sin();
+		let reference = (direction - axis * direction.scalar(axis)).normalize();
+
+		Rotation {
+			axis,
+			normal,
+			reference,
+			period,
+			year
+		}
+	}
+
+	pub fn axis(&self) -> Vector3d<f64> {
+		self.axis
+	}
+
+	/// Sidereal rotation period, in s.
+	pub fn period(&self) -> f64 {
+		self.period
+	}
+
+	/// Angle between the rotation axis and the orbit normal, in radians.
+	pub fn tilt(&self) -> f64 {
+		(self.axis * self.normal).len().atan2(self.axis.scalar(self.normal))
+	}
+
+	/// Mean solar day, time between two noons, in s.
+	pub fn solar_day(&self) -> f64 {
+		1.0 / (1.0 / self.period - 1.0 / self.year)
+	}
+
+	/// Rotation angle of the prime meridian at the given time, in radians.
+	pub fn angle(&self, t: f64) -> f64 {
+		(2.0 * PI * t / self.period).rem_euclid(2.0 * PI)
+	}
+
+	/// Axes of the body-fixed frame in the inertial frame, at the given time.
+	fn axes(&self, t: f64) -> (Vector3d<f64>, Vector3d<f64>) {
+		let (sin, cos) = self.angle(t).sin_cos();
+		let y = self.axis * self.reference;
+		(self.reference * cos + y * sin, y * cos - self.reference * sin)
+	}
+
+	/// Convert a body-fixed vector to the inertial frame.
+	pub fn to_inertial(&self, v: Vector3d<f64>, t: f64) -> Vector3d<f64> {
+		let (x, y) = self.axes(t);
+		x * v.x + y * v.y + self.axis * v.z
+	}
+
+	/// Convert an inertial vector to the body-fixed frame.
+	pub fn to_local(&self, v: Vector3d<f64>, t: f64) -> Vector3d<f64> {
+		let (x, y) = self.axes(t);
+		Vector3d::new(v.scalar(x), v.scalar(y), v.scalar(self.axis))
+	}
+
+	/// Point of the surface where the star is at the zenith.
+	///
+	/// `sun` is the direction of the star from the body center, in the inertial frame.
+	pub fn subsolar(&self, sun: Vector3d<f64>, t: f64) -> Position<f64> {
+		self.to_local(sun, t).into()
+	}
+
+	/// Elevation above the horizon and azimuth (clockwise from the north) of the star, in radians,
+	/// seen from the given surface position.
+	pub fn solar_angles(&self, p: &Position<f64>, sun: Vector3d<f64>, t: f64) -> (f64, f64) {
+		let s = self.to_local(sun, t).normalize();
+		let elevation = s.scalar(p.as_unit()).clamp(-1.0, 1.0).asin();
+		let azimuth = s.scalar(p.east()).atan2(s.scalar(p.north())).rem_euclid(2.0 * PI);
+		(elevation, azimuth)
+	}
+
+	/// Local solar time at the given position, as a fraction of the solar day (`0.5` being noon).
+	pub fn local_time(&self, p: &Position<f64>, sun: Vector3d<f64>, t: f64) -> f64 {
+		let hour_angle = p.longitude() - self.subsolar(sun, t).longitude();
+		(hour_angle / (2.0 * PI) + 0.5).rem_euclid(1.0)
+	}
+
+	/// Declination of the star, the latitude of the subsolar point, in radians.
+	pub fn declination(&self, sun: Vector3d<f64>) -> f64 {
+		sun.normalize().scalar(self.axis).clamp(-1.0, 1.0).asin()
+	}
+
+	/// Duration of the daylight at the given latitude (in radians), in s.
+	///
+	/// The declination is assumed constant during the day.
+	pub fn day_length(&self, latitude: f64, sun: Vector3d<f64>) -> f64 {
+		let cos = -latitude.tan() * self.declination(sun).tan();
+		self.solar_day() * cos.clamp(-1.0, 1.0).acos() / PI
+	}
+
+	/// Position of the star along its apparent yearly path, from the northern spring equinox, in `[0, 2PI)`.
+	pub fn solar_longitude(&self, sun: Vector3d<f64>) -> f64 {
+		let toward = self.axis - self.normal * self.axis.scalar(self.normal);
+		if toward.len() < 1e-12 {
+			// No tilt, no seasons.
+			return 0.0
+		}
+
+		let solstice = toward.normalize();
+		let equinox = solstice * self.normal;
+		sun.scalar(solstice).atan2(sun.scalar(equinox)).rem_euclid(2.0 * PI)
+	}
+
+	/// Season of the hemisphere of the given latitude.
+	pub fn season(&self, latitude: f64, sun: Vector3d<f64>) -> Season {
+		let quarter = (self.solar_longitude(sun) / (PI / 2.0)) as u32 % 4;
+		let quarter = if latitude < 0.0 { (quarter + 2) % 4 } else { quarter };
+		match quarter {
+			0 => Season::Spring,
+			1 => Season::Summer,
+			2 => Season::Autumn,
+			_ => Season::Winter
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::f64::consts::PI;
+	use crate::location::{Vector3d, Position};
+	use super::{Rotation, Season};
+
+	#[test]
+	fn earth() {
+		let year = 365.256 * 86_400.0;
+		let normal = Vector3d::new(0.0, 0.0, 1.0);
+		let rotation = Rotation::new(normal, 0.409, Vector3d::new(1.0, 0.0, 0.0), 86_164.1, year);
+		assert!((rotation.tilt() - 0.409).abs() < 1e-9);
+		assert!((rotation.solar_day() - 86_400.0).abs() < 1.0);
+
+		// Northern summer solstice: the star lies in the tilt direction.
+		let sun = Vector3d::new(1.0, 0.0, 0.0);
+		assert_eq!(rotation.season(0.8, sun), Season::Summer);
+		assert_eq!(rotation.season(-0.8, sun), Season::Winter);
+		assert!((rotation.declination(sun) - 0.409).abs() < 1e-9);
+		assert!((rotation.subsolar(sun, 0.0).latitude() - 0.409).abs() < 1e-9);
+		assert!(rotation.day_length(0.8, sun) > 15.0 * 3600.0);
+		assert!((rotation.day_length(0.0, sun) - 43_200.0).abs() < 1.0);
+		assert_eq!(rotation.day_length(1.4, sun), rotation.solar_day());
+		assert_eq!(rotation.season(0.8, Vector3d::new(0.0, 1.0, 0.0)), Season::Autumn);
+
+		// The star is at the zenith over the subsolar point, and rises in the east.
+		let t = 12_345.0;
+		let noon = rotation.subsolar(sun, t);
+		let (elevation, _) = rotation.solar_angles(&noon, sun, t);
+		assert!((elevation - PI / 2.0).abs() < 1e-6);
+		assert!((rotation.local_time(&noon, sun, t) - 0.5).abs() < 1e-9);
+
+		let morning = Position::from_geographic(0.0, noon.longitude() - 0.5);
+		let (elevation, azimuth) = rotation.solar_angles(&morning, sun, t);
+		assert!(elevation > 0.0 && azimuth > 0.0 && azimuth < PI);
+		assert!(rotation.local_time(&morning, sun, t) < 0.5);
+
+		// The surface turns eastward.
+		let p = Vector3d::new(1.0, 0.0, 0.0);
+		let (a, b) = (rotation.to_inertial(p, 0.0), rotation.to_inertial(p, 100.0));
+		assert!((b - a).scalar(rotation.axis() * a) > 0.0);
+		assert!((rotation.to_local(b, 100.0) - p).len() < 1e-12)
+	}
+}
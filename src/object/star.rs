@@ -0,0 +1,145 @@
+use glam::Vec3;
+use super::body::{Body, Composition};
+
+/// Stefan-Boltzmann constant, in W/m²/K⁴.
+pub const SIGMA: f64 = 5.670_374e-8;
+
+/// Solar mass, in kg.
+pub const SOLAR_MASS: f64 = 1.989e30;
+
+/// Solar radius, in m.
+pub const SOLAR_RADIUS: f32 = 6.957e8;
+
+/// Solar luminosity, in W.
+pub const SOLAR_LUMINOSITY: f64 = 3.828e26;
+
+/// Spectral class of a main sequence star.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Class {
+	O,
+	B,
+	A,
+	F,
+	G,
+	K,
+	M
+}
+
+impl Class {
+	/// Range of the masses of the class, in solar masses.
+	pub fn mass_range(&self) -> (f32, f32) {
+		match self {
+			Class::O => (16.0, 60.0),
+			Class::B => (2.1, 16.0),
+			Class::A => (1.4, 2.1),
+			Class::F => (1.04, 1.4),
+			Class::G => (0.8, 1.04),
+			Class::K => (0.45, 0.8),
+			Class::M => (0.08, 0.45)
+		}
+	}
+
+	/// Class of a main sequence star of the given mass (in solar masses).
+	pub fn from_mass(mass: f32) -> Class {
+		[Class::O, Class::B, Class::A, Class::F, Class::G, Class::K].iter().copied().find(|c| mass >= c.mass_range().0).unwrap_or(Class::M)
+	}
+}
+
+/// Global parameters of a star.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Descriptor {
+	/// Mass, in kg.
+	pub mass: f64,
+
+	/// Photosphere radius, in m.
+	pub radius: f32,
+
+	/// Emitted power, in W.
+	pub luminosity: f64,
+
+	pub composition: Composition
+}
+
+impl Descriptor {
+	/// Main sequence star of the given mass (in solar masses),
+	/// with the usual mass-luminosity and mass-radius relations.
+	pub fn main_sequence(mass: f32) -> Descriptor {
+		Descriptor {
+			mass: mass as f64 * SOLAR_MASS,
+			radius: SOLAR_RADIUS * mass.powf(0.8),
+			luminosity: SOLAR_LUMINOSITY * (mass as f64).powf(3.5),
+			..Descriptor::default()
+		}
+	}
+
+	pub fn class(&self) -> Class {
+		Class::from_mass((self.mass / SOLAR_MASS) as f32)
+	}
+}
+
+impl Default for Descriptor {
+	/// Sun-like star.
+	fn default() -> Descriptor {
+		Descriptor {
+			mass: SOLAR_MASS,
+			radius: SOLAR_RADIUS,
+			luminosity: SOLAR_LUMINOSITY,
+			composition: Composition { metal: 0.0, rock: 0.014, ice: 0.0, gas: 0.986 }
+		}
+	}
+}
+
+pub struct Star {
+	d: Descriptor
+}
+
+impl Star {
+	pub fn new(d: Descriptor) -> Star {
+		Star {
+			d
+		}
+	}
+
+	pub fn descriptor(&self) -> &Descriptor {
+		&self.d
+	}
+
+	/// Effective temperature of the photosphere, in K.
+	pub fn temperature(&self) -> f32 {
+		let r = self.d.radius as f64;
+		(self.d.luminosity / (4.0 * std::f64::consts::PI * r * r * SIGMA)).powf(0.25) as f32
+	}
+
+	/// Radiation flux at the given distance, in W/m².
+	pub fn flux(&self, distance: f64) -> f64 {
+		self.d.luminosity / (4.0 * std::f64::consts::PI * distance * distance)
+	}
+}
+
+impl Default for Star {
+	fn default() -> Star {
+		Star::new(Descriptor::default())
+	}
+}
+
+impl Body for Star {
+	fn mass(&self) -> f64 {
+		self.d.mass
+	}
+
+	fn composition(&self) -> Composition {
+		self.d.composition
+	}
+
+	fn luminosity(&self) -> f64 {
+		self.d.luminosity
+	}
+
+	fn surface_radius(&self, _dir: Vec3) -> f32 {
+		self.d.radius
+	}
+
+	fn bounding_radius(&self) -> f32 {
+		self.d.radius
+	}
+}
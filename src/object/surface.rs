@@ -0,0 +1,380 @@
+use glam::Vec3;
+use crate::topology::Cell;
+use super::body::Body;
+
+/// Number of steps used to find a surface point or a crossing.
+const ITERATIONS: usize = 32;
+
+/// Default bound of the surface slopes (see [`Surface::max_slope`]), about 72°.
+pub const MAX_SLOPE: f32 = 3.0;
+
+/// Intersection with a surface.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hit {
+	/// Contact point.
+	pub point: Vec3,
+
+	/// Surface normal at the contact point.
+	pub normal: Vec3,
+
+	/// Distance travelled from the start of the query, in m.
+	pub distance: f32
+}
+
+/// Star-shaped surface around a body center, seen from the center as a function of the direction.
+///
+/// Positions are relative to the body center, in the body-fixed frame, in m.
+pub trait Surface {
+	/// Distance from the center to the surface in the given direction.
+	fn radius(&self, dir: Vec3) -> f32;
+
+	/// Radius of a sphere containing the whole surface.
+	fn bounding_radius(&self) -> f32;
+
+	/// Tangent of the steepest slope of the surface.
+	///
+	/// It bounds the marching steps of the casts: steeper features may be stepped over.
+	fn max_slope(&self) -> f32 {
+		MAX_SLOPE
+	}
+
+	/// Outward surface normal in the given direction.
+	///
+	/// Computed by finite differences by default.
+	fn normal(&self, dir: Vec3) -> Vec3 {
+		let dir = dir.normalize();
+		let r = self.radius(dir);
+		let helper = if dir.x.abs() < 0.9 { Vec3::new(1.0, 0.0, 0.0) } else { Vec3::new(0.0, 1.0, 0.0) };
+		let u = dir.cross(helper).normalize();
+		let v = dir.cross(u);
+
+		// Radial derivatives, by central differences.
+		let epsilon = 1e-4;
+		let slope = |t: Vec3| (self.radius((dir + t * epsilon).normalize()) - self.radius((dir - t * epsilon).normalize())) / (2.0 * epsilon * r);
+		(dir - u * slope(u) - v * slope(v)).normalize()
+	}
+
+	/// Point of the surface directly above or below `p`.
+	fn vertical(&self, p: Vec3) -> Vec3 {
+		let dir = p.normalize();
+		dir * self.radius(dir)
+	}
+
+	/// Closest surface point to `p`, by successive projections on the tangent planes.
+	fn closest_point(&self, p: Vec3) -> Vec3 {
+		let mut q = self.vertical(p);
+		for _ in 0..ITERATIONS {
+			let n = self.normal(q);
+			let next = self.vertical(p - n * (p - q).dot(n));
+			if (next - q).length_squared() < 1e-6 {
+				return next
+			}
+
+			q = next
+		}
+
+		q
+	}
+
+	/// Signed distance from `p` to the surface, positive above.
+	///
+	/// Each call searches the [`closest_point`](Surface::closest_point), sampling the radius up to a hundred times.
+	fn signed_distance(&self, p: Vec3) -> f32 {
+		let q = self.closest_point(p);
+		let d = (p - q).length();
+		if p.length() < q.length() {
+			-d
+		} else {
+			d
+		}
+	}
+
+	/// First intersection of the segment from `a` to `b` with the surface, coming from above.
+	fn intersect_segment(&self, a: Vec3, b: Vec3) -> Option<Hit> {
+		self.sphere_cast(a, b, 0.0)
+	}
+
+	/// First intersection of the ray with the surface, coming from above.
+	///
+	/// Marches against [`radius`](Surface::radius), so the hit follows the full resolution of the surface.
+	fn raycast(&self, origin: Vec3, dir: Vec3) -> Option<Hit> {
+		let far = origin.length() + self.bounding_radius();
+		self.intersect_segment(origin, origin + dir.normalize() * far)
+	}
+
+	/// First contact of a sphere of the given radius moving from `a` to `b` with the surface.
+	///
+	/// The hit distance is the distance travelled by the center of the sphere.
+	/// Far from the surface, steps are bounded by the vertical gap and the [`max_slope`](Surface::max_slope),
+	/// and the exact [`signed_distance`](Surface::signed_distance) is only used near the surface.
+	fn sphere_cast(&self, a: Vec3, b: Vec3, radius: f32) -> Option<Hit> {
+		let length = (b - a).length();
+		if length == 0.0 {
+			return None
+		}
+
+		let dir = (b - a) / length;
+		let outer = self.bounding_radius() + radius;
+
+		// Skip the part of the segment outside of the bounding sphere.
+		let closest = -a.dot(dir);
+		let offset = a.length_squared() - closest * closest;
+		if offset > outer * outer {
+			return None
+		}
+
+		let half = (outer * outer - offset).sqrt();
+		let (start, end) = ((closest - half).max(0.0), (closest + half).min(length));
+		if start > end {
+			return None
+		}
+
+		// With slopes bounded by `s`, the distance to the surface is at least the vertical gap divided by `sqrt(1 + s²)`.
+		let slope = self.max_slope();
+		let lipschitz = (1.0 + slope * slope).sqrt();
+		let vertical = |t: f32| {
+			let p = a + dir * t;
+			p.length() - self.radius(p.normalize())
+		};
+
+		// Exact clearance (the vertical gap is enough to find the crossing of a segment).
+		let gap = |t: f32| {
+			if radius > 0.0 {
+				self.signed_distance(a + dir * t) - radius
+			} else {
+				vertical(t)
+			}
+		};
+
+		let min_step = ((end - start) / 4096.0).max(1e-3);
+		let mut previous = None;
+		let mut t = start;
+		loop {
+			let mut clearance = vertical(t) / lipschitz - radius;
+			if clearance <= 0.0 {
+				clearance = gap(t);
+				if clearance <= 0.0 {
+					return match previous {
+						Some(low) => {
+							// Bisect the crossing.
+							let (mut low, mut high) = (low, t);
+							for _ in 0..ITERATIONS {
+								let mid = (low + high) / 2.0;
+								if gap(mid) > 0.0 {
+									low = mid
+								} else {
+									high = mid
+								}
+							}
+
+							Some(hit(self, a + dir * high, high, radius))
+						},
+						None if start > 0.0 => Some(hit(self, a + dir * start, start, radius)),
+						None => None
+					}
+				}
+			}
+
+			if t >= end {
+				return None
+			}
+
+			previous = Some(t);
+			t = (t + clearance.max(min_step)).min(end)
+		}
+	}
+}
+
+/// Contact of a sphere of the given radius centered at `center`.
+fn hit<S: ?Sized + Surface>(surface: &S, center: Vec3, distance: f32, radius: f32) -> Hit {
+	let normal = surface.normal(center);
+	Hit {
+		point: surface.vertical(center - normal * radius),
+		normal,
+		distance
+	}
+}
+
+/// Ray-triangle intersection (Möller-Trumbore), for both faces of the triangle.
+///
+/// Return the distance along the ray (whose direction is normalized).
+pub fn ray_triangle(origin: Vec3, dir: Vec3, [a, b, c]: [Vec3; 3]) -> Option<f32> {
+	let dir = dir.normalize();
+	let (e1, e2) = (b - a, c - a);
+	let p = dir.cross(e2);
+	let det = e1.dot(p);
+	if det.abs() < 1e-12 * e1.length_squared() {
+		return None
+	}
+
+	let s = (origin - a) / det;
+	let u = s.dot(p);
+	if !(0.0..=1.0).contains(&u) {
+		return None
+	}
+
+	let q = s.cross(e1);
+	let v = dir.dot(q);
+	if v < 0.0 || u + v > 1.0 {
+		return None
+	}
+
+	let t = e2.dot(q);
+	if t >= 0.0 {
+		Some(t)
+	} else {
+		None
+	}
+}
+
+/// Closest intersection of a ray with the triangles of a cell hierarchy.
+///
+/// Cells are descended through their bounding boxes between `min_radius` and `max_radius`,
+/// until `leaf` gives the triangle of the cell, which is then tested exactly.
+/// Return the leaf cell and the distance along the ray (whose direction is normalized).
+pub fn raycast_cells<F>(origin: Vec3, dir: Vec3, min_radius: f32, max_radius: f32, leaf: F) -> Option<(Cell, f32)> where F: Fn(Cell) -> Option<[Vec3; 3]> {
+	fn visit<F: Fn(Cell) -> Option<[Vec3; 3]>>(cell: Cell, origin: Vec3, dir: Vec3, radii: (f32, f32), leaf: &F, best: &mut Option<(Cell, f32)>) {
+		match cell.obb(radii.0, radii.1).intersects_ray(origin, dir) {
+			Some(t) if !matches!(best, Some((_, b)) if t > *b) => (),
+			_ => return
+		}
+
+		match leaf(cell) {
+			Some(triangle) => {
+				if let Some(t) = ray_triangle(origin, dir, triangle) {
+					if !matches!(best, Some((_, b)) if t >= *b) {
+						*best = Some((cell, t))
+					}
+				}
+			},
+			None => {
+				for child in cell.children().iter() {
+					visit(*child, origin, dir, radii, leaf, best)
+				}
+			}
+		}
+	}
+
+	let dir = dir.normalize();
+	let mut best = None;
+	for cell in Cell::all(0) {
+		visit(cell, origin, dir, (min_radius, max_radius), &leaf, &mut best)
+	}
+
+	best
+}
+
+/// Exact surface of a body (see [`Body::surface_radius`]).
+pub struct Analytic<'a, B: ?Sized + Body>(pub &'a B);
+
+impl<'a, B: ?Sized + Body> Surface for Analytic<'a, B> {
+	fn radius(&self, dir: Vec3) -> f32 {
+		self.0.surface_radius(dir)
+	}
+
+	fn bounding_radius(&self) -> f32 {
+		self.0.bounding_radius()
+	}
+}
+
+/// Surface made of triangles, such as a level of detail of a rendered mesh.
+///
+/// `locate` gives the triangle (in any winding order) crossed by the half line from the center in the given direction.
+pub struct Triangles<F: Fn(Vec3) -> Option<[Vec3; 3]>> {
+	locate: F,
+	bounding_radius: f32
+}
+
+impl<F: Fn(Vec3) -> Option<[Vec3; 3]>> Triangles<F> {
+	pub fn new(locate: F, bounding_radius: f32) -> Triangles<F> {
+		Triangles {
+			locate,
+			bounding_radius
+		}
+	}
+}
+
+impl<F: Fn(Vec3) -> Option<[Vec3; 3]>> Surface for Triangles<F> {
+	/// Intersection of the half line with the plane of the triangle.
+	fn radius(&self, dir: Vec3) -> f32 {
+		let dir = dir.normalize();
+		match (self.locate)(dir) {
+			Some([a, b, c]) => {
+				let n = (b - a).cross(c - a);
+				a.dot(n) / dir.dot(n)
+			},
+			None => self.bounding_radius
+		}
+	}
+
+	fn bounding_radius(&self) -> f32 {
+		self.bounding_radius
+	}
+
+	/// Normal of the triangle, each triangle being flat.
+	fn normal(&self, dir: Vec3) -> Vec3 {
+		match (self.locate)(dir.normalize()) {
+			Some([a, b, c]) => {
+				let n = (b - a).cross(c - a).normalize();
+				if n.dot(dir) < 0.0 { -n } else { n }
+			},
+			None => dir.normalize()
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use glam::Vec3;
+	use crate::topology::{Cell, Mesh};
+	use crate::object::{Planet, planet::Descriptor};
+	use super::{Surface, Analytic, Triangles, raycast_cells};
+
+	#[test]
+	fn queries() {
+		let planet = Planet::from_seed(1, Descriptor { radius: 1000.0, oblateness: 0.0, ..Descriptor::default() });
+		let sphere = Analytic(&planet);
+		let p = Vec3::new(600.0, 800.0, 0.0) * 1.5;
+		assert!((sphere.signed_distance(p) - 500.0).abs() < 1e-2);
+		assert!((sphere.signed_distance(p * 0.5) + 250.0).abs() < 1e-2);
+		assert!((sphere.closest_point(p) - Vec3::new(600.0, 800.0, 0.0)).length() < 1e-2);
+
+		let hit = sphere.intersect_segment(Vec3::new(0.0, 0.0, 3000.0), Vec3::new(0.0, 0.0, -3000.0)).unwrap();
+		assert!((hit.distance - 2000.0).abs() < 1e-2);
+		assert!((hit.normal - Vec3::new(0.0, 0.0, 1.0)).length() < 1e-3);
+		assert!(sphere.intersect_segment(Vec3::new(0.0, 2000.0, 3000.0), Vec3::new(0.0, 2000.0, -3000.0)).is_none());
+
+		let hit = sphere.sphere_cast(Vec3::new(-3000.0, 0.0, 0.0), Vec3::new(3000.0, 0.0, 0.0), 100.0).unwrap();
+		assert!((hit.distance - 1900.0).abs() < 1e-2);
+		assert!((hit.point - Vec3::new(-1000.0, 0.0, 0.0)).length() < 1e-2);
+
+		// Flat triangles of an icosphere.
+		let mesh = Mesh::new(3);
+		let triangles = Triangles::new(|dir| {
+			let f = mesh.face(Cell::locate(dir, 3));
+			Some([mesh.vertex(f[0]) * 1000.0, mesh.vertex(f[1]) * 1000.0, mesh.vertex(f[2]) * 1000.0])
+		}, 1000.0);
+
+		let a = Vec3::new(200.0, -300.0, 2000.0);
+		let hit = triangles.intersect_segment(a, -a).unwrap();
+		assert!(hit.distance > 1000.0 && hit.point.length() <= 1000.0 && hit.point.length() > 990.0);
+		assert!(triangles.signed_distance(hit.point).abs() < 1e-1);
+		let q = triangles.closest_point(a);
+		assert!((triangles.signed_distance(a) - (a - q).length()).abs() < 1e-1);
+		assert!(triangles.normal(a).dot(a.normalize()) > 0.99);
+
+		// Hierarchical ray casting gives the same hit, with the cell under it.
+		let leaf = |cell: Cell| if cell.depth() == 3 {
+			let f = mesh.face(cell);
+			Some([mesh.vertex(f[0]) * 1000.0, mesh.vertex(f[1]) * 1000.0, mesh.vertex(f[2]) * 1000.0])
+		} else {
+			None
+		};
+
+		let (cell, t) = raycast_cells(a, -a, 1000.0, 1000.0, leaf).unwrap();
+		assert!((t - hit.distance).abs() < 1e-2);
+		assert_eq!(cell, Cell::locate(hit.point, 3));
+		assert!(raycast_cells(a, a, 1000.0, 1000.0, leaf).is_none());
+		assert!((sphere.raycast(a, -a).unwrap().distance - (a.length() - 1000.0)).abs() < 1e-2)
+	}
+}
@@ -0,0 +1,170 @@
+use crate::location::Vector3d;
+use super::{Object, Body};
+use super::orbit::{Orbit, Elements, State};
+use super::rotation::Rotation;
+
+struct Entry {
+	object: Object,
+	parent: Option<usize>,
+	orbit: Option<Orbit>,
+	children: Vec<usize>
+}
+
+/// Star system, a hierarchy of objects orbiting their parent.
+///
+/// Objects are identified by their index in the system.
+/// The root (usually a star) stays at the origin.
+pub struct StarSystem {
+	entries: Vec<Entry>
+}
+
+impl StarSystem {
+	/// Index of the root object.
+	pub const ROOT: usize = 0;
+
+	pub fn new(root: Object) -> StarSystem {
+		StarSystem {
+			entries: vec![Entry {
+				object: root,
+				parent: None,
+				orbit: None,
+				children: Vec::new()
+			}]
+		}
+	}
+
+	/// Add an object orbiting the given parent, and return its index.
+	pub fn add(&mut self, object: Object, parent: usize, elements: Elements) -> usize {
+		let orbit = Orbit::new(elements, self.entries[parent].object.mass(), object.mass());
+		let index = self.entries.len();
+		self.entries.push(Entry {
+			object,
+			parent: Some(parent),
+			orbit: Some(orbit),
+			children: Vec::new()
+		});
+
+		self.entries[parent].children.push(index);
+		index
+	}
+
+	pub fn len(&self) -> usize {
+		self.entries.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.entries.is_empty()
+	}
+
+	pub fn object(&self, index: usize) -> &Object {
+		&self.entries[index].object
+	}
+
+	pub fn object_mut(&mut self, index: usize) -> &mut Object {
+		&mut self.entries[index].object
+	}
+
+	pub fn parent(&self, index: usize) -> Option<usize> {
+		self.entries[index].parent
+	}
+
+	pub fn children(&self, index: usize) -> &[usize] {
+		&self.entries[index].children
+	}
+
+	/// Orbit of the object around its parent (`None` for the root).
+	pub fn orbit(&self, index: usize) -> Option<&Orbit> {
+		self.entries[index].orbit.as_ref()
+	}
+
+	/// Position and velocity of the object relative to the root at the given time, in m and m/s.
+	pub fn state(&self, index: usize, t: f64) -> State {
+		let mut state = State {
+			position: Vector3d::new(0.0, 0.0, 0.0),
+			velocity: Vector3d::new(0.0, 0.0, 0.0)
+		};
+
+		let mut i = index;
+		while let (Some(parent), Some(orbit)) = (self.entries[i].parent, &self.entries[i].orbit) {
+			let local = orbit.state(t);
+			state.position = state.position + local.position;
+			state.velocity = state.velocity + local.velocity;
+			i = parent
+		}
+
+		state
+	}
+
+	/// Position of the object relative to the root at the given time, in m.
+	pub fn position(&self, index: usize, t: f64) -> Vector3d<f64> {
+		self.state(index, t).position
+	}
+
+	/// Velocity of the object relative to the root at the given time, in m/s.
+	pub fn velocity(&self, index: usize, t: f64) -> Vector3d<f64> {
+		self.state(index, t).velocity
+	}
+
+	/// Ancestor of the object directly orbiting the root (the object itself for a planet).
+	pub fn primary(&self, index: usize) -> Option<usize> {
+		let mut i = index;
+		while let Some(parent) = self.entries[i].parent {
+			if parent == StarSystem::ROOT {
+				return Some(i)
+			}
+
+			i = parent
+		}
+
+		None
+	}
+
+	/// Direction of the root (the star) seen from the object at the given time (unit vector).
+	pub fn sun_direction(&self, index: usize, t: f64) -> Vector3d<f64> {
+		(-self.position(index, t)).normalize()
+	}
+
+	/// Rotation of a planet or moon.
+	///
+	/// Its axis is tilted from the normal of the orbit around the star, toward the ascending node.
+	pub fn rotation(&self, index: usize) -> Option<Rotation> {
+		let d = match &self.entries[index].object {
+			Object::Planet(planet) | Object::Moon(planet) => planet.descriptor(),
+			_ => return None
+		};
+
+		let orbit = self.orbit(self.primary(index)?)?;
+		Some(Rotation::new(orbit.normal(), d.axial_tilt as f64, orbit.node(), d.rotation_period as f64, orbit.period()))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::object::{Object, Star, Planet, orbit::Elements};
+	use crate::object::planet::Descriptor;
+	use super::StarSystem;
+
+	#[test]
+	fn nested() {
+		let mut system = StarSystem::new(Object::Star(Star::default()));
+		let earth = system.add(Object::Planet(Box::new(Planet::from_seed(1, Descriptor::default()))), StarSystem::ROOT, Elements::circular(1.496e11));
+		let moon = Descriptor { radius: 1_737_000.0, mass: 7.342e22, ..Descriptor::default() };
+		let moon = system.add(Object::Moon(Box::new(Planet::from_seed(2, moon))), earth, Elements::circular(3.844e8));
+
+		assert_eq!(system.children(earth), &[moon]);
+		assert!((system.orbit(moon).unwrap().period() / 86_400.0 - 27.3).abs() < 0.1);
+
+		let t = 1e6;
+		let relative = system.position(moon, t) - system.position(earth, t);
+		assert!((relative.len() - 3.844e8).abs() < 1.0);
+		let speed = (system.velocity(moon, t) - system.velocity(earth, t)).len();
+		assert!((speed - 1024.0).abs() < 5.0);
+
+		// The moon shares the seasons of the earth.
+		assert_eq!(system.primary(moon), Some(earth));
+		let (a, b) = (system.rotation(earth).unwrap(), system.rotation(moon).unwrap());
+		assert!((a.solar_day() - 86_400.0).abs() < 10.0);
+		assert_eq!(a.season(1.0, system.sun_direction(earth, t)), b.season(1.0, system.sun_direction(moon, t)));
+		assert!(system.rotation(StarSystem::ROOT).is_none())
+	}
+}
@@ -0,0 +1,125 @@
+//! Deterministic random numbers.
+//!
+//! Everything procedurally generated is derived from a single seed,
+//! through independent streams split by key (layer name, cell, etc.)
+//! so that the result does not depend on the generation order.
+//!
+//! Only integer arithmetic and correctly rounded floating point operations are used,
+//! so that the streams are bit-identical on any machine.
+//! The generation code built on them calls the [`libm`] transcendental functions
+//! instead of the platform ones, which are not correctly rounded,
+//! so that a planet also regenerates bit-identically.
+
+use glam::Vec3;
+use crate::topology::Cell;
+
+/// Splittable pseudo random number generator (SplitMix64).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rng {
+	state: u64
+}
+
+const GOLDEN_GAMMA: u64 = 0x9e3779b97f4a7c15;
+
+/// SplitMix64 finalizer.
+fn mix(mut z: u64) -> u64 {
+	z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+	z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+	z ^ (z >> 31)
+}
+
+impl Rng {
+	pub fn new(seed: u64) -> Rng {
+		Rng {
+			state: mix(seed)
+		}
+	}
+
+	/// Independent stream identified by the given key.
+	///
+	/// It only depends on the current state and the key.
+	pub fn split(&self, key: u64) -> Rng {
+		Rng {
+			state: mix(self.state ^ mix(key.wrapping_add(GOLDEN_GAMMA)))
+		}
+	}
+
+	/// Independent stream identified by the given string key.
+	pub fn split_str(&self, key: &str) -> Rng {
+		// FNV-1a hash.
+		let mut hash: u64 = 0xcbf29ce484222325;
+		for b in key.bytes() {
+			hash = (hash ^ b as u64).wrapping_mul(0x100000001b3);
+		}
+
+		self.split(hash)
+	}
+
+	/// Independent stream of the given cell.
+	pub fn cell(&self, cell: Cell) -> Rng {
+		self.split(cell.id())
+	}
+
+	pub fn next_u64(&mut self) -> u64 {
+		self.state = self.state.wrapping_add(GOLDEN_GAMMA);
+		mix(self.state)
+	}
+
+	pub fn next_u32(&mut self) -> u32 {
+		(self.next_u64() >> 32) as u32
+	}
+
+	/// Uniform number in `[0, 1)`.
+	pub fn next_f32(&mut self) -> f32 {
+		(self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+	}
+
+	/// Uniform number in `[0, 1)`.
+	pub fn next_f64(&mut self) -> f64 {
+		(self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+	}
+
+	/// Uniform number in `[min, max)`.
+	pub fn range(&mut self, min: f32, max: f32) -> f32 {
+		min + (max - min) * self.next_f32()
+	}
+
+	/// Uniform integer in `[0, n)`.
+	pub fn below(&mut self, n: u32) -> u32 {
+		((self.next_u32() as u64 * n as u64) >> 32) as u32
+	}
+
+	/// Uniformly distributed unit vector.
+	pub fn unit_vector(&mut self) -> Vec3 {
+		loop {
+			let v = Vec3::new(self.range(-1.0, 1.0), self.range(-1.0, 1.0), self.range(-1.0, 1.0));
+			let l = v.length_squared();
+			if l > 1e-6 && l <= 1.0 {
+				return v / l.sqrt()
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::Rng;
+	use crate::topology::Cell;
+
+	#[test]
+	fn reproducible() {
+		let mut rng = Rng::new(42);
+		assert_eq!(rng.next_u64(), 0x989b3f130a063869);
+		assert_eq!(Rng::new(42).split_str("elevation").cell(Cell::new(3, 17)).next_u32(), 0x63851ff8);
+	}
+
+	#[test]
+	fn independent_streams() {
+		let rng = Rng::new(7);
+		let mut a = rng.split(1);
+		let mut b = rng.split(2);
+		assert_ne!(a.next_u64(), b.next_u64());
+		assert_eq!(rng.split(1), rng.split(1));
+		assert_ne!(Rng::new(7).cell(Cell::new(0, 1)), Rng::new(7).cell(Cell::new(1, 1)));
+	}
+}
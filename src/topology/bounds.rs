@@ -0,0 +1,346 @@
+use glam::Vec3;
+
+/// Spherical cap, extruded between two radii.
+///
+/// This is a conservative bounding volume for a region of the surface of a spherical object,
+/// where `min_radius` and `max_radius` account for the terrain displacement.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cap {
+	/// Direction of the cap center (unit vector).
+	pub center: Vec3,
+
+	/// Angular radius of the cap, in radians.
+	pub angle: f32,
+
+	/// Minimum distance to the sphere center.
+	pub min_radius: f32,
+
+	/// Maximum distance to the sphere center.
+	pub max_radius: f32
+}
+
+impl Cap {
+	pub fn new(center: Vec3, angle: f32, min_radius: f32, max_radius: f32) -> Cap {
+		Cap {
+			center: center.normalize(),
+			angle,
+			min_radius,
+			max_radius
+		}
+	}
+
+	/// Cap covering the whole sphere.
+	pub fn whole(min_radius: f32, max_radius: f32) -> Cap {
+		Cap::new(Vec3::unit_z(), std::f32::consts::PI, min_radius, max_radius)
+	}
+
+	/// Smallest cap centered on the mean direction of the given points, containing them all.
+	///
+	/// The points are taken as directions, whatever their length.
+	pub fn from_points(points: &[Vec3], min_radius: f32, max_radius: f32) -> Cap {
+		let sum = points.iter().fold(Vec3::zero(), |s, p| s + p.normalize());
+		if sum.length_squared() < 1e-12 {
+			return Cap::whole(min_radius, max_radius)
+		}
+
+		let center = sum.normalize();
+		let angle = points.iter().map(|p| angle(center, *p)).fold(0.0f32, f32::max);
+
+		// Keep rounding errors from excluding the points.
+		Cap::new(center, angle + 1e-5, min_radius, max_radius)
+	}
+
+	/// Bounding cap of a surface region (spherical triangle), between the given radii.
+	///
+	/// The cap also contains the flat triangle joining the corners of the region.
+	pub fn from_region(corners: [Vec3; 3], min_radius: f32, max_radius: f32) -> Cap {
+		let cap = Cap::from_points(&corners, min_radius, max_radius);
+		Cap {
+			min_radius: min_radius * libm::cosf(cap.angle),
+			..cap
+		}
+	}
+
+	/// Checks if the cap contains the given direction.
+	pub fn contains(&self, dir: Vec3) -> bool {
+		angle(self.center, dir) <= self.angle
+	}
+
+	/// Checks if the cap contains the given point.
+	pub fn contains_point(&self, p: Vec3) -> bool {
+		let r = p.length();
+		r >= self.min_radius && r <= self.max_radius && self.contains(p)
+	}
+
+	/// Checks if the two caps intersect.
+	pub fn intersects(&self, other: &Cap) -> bool {
+		self.min_radius <= other.max_radius && other.min_radius <= self.max_radius
+			&& angle(self.center, other.center) <= self.angle + other.angle
+	}
+
+	/// Checks if the cap intersects the given surface region (spherical triangle), between the given radii.
+	pub fn intersects_region(&self, corners: [Vec3; 3], min_radius: f32, max_radius: f32) -> bool {
+		if self.min_radius > max_radius || min_radius > self.max_radius {
+			return false
+		}
+
+		if corners.iter().any(|c| self.contains(*c)) {
+			return true
+		}
+
+		// The center of the cap is inside the triangle (and not inside its antipode).
+		let [a, b, c] = corners;
+		let s = [a.cross(b).dot(self.center), b.cross(c).dot(self.center), c.cross(a).dot(self.center)];
+		let inside = s.iter().all(|s| *s >= 0.0) || s.iter().all(|s| *s <= 0.0);
+		if inside && (a + b + c).dot(self.center) > 0.0 {
+			return true
+		}
+
+		// The cap intersects an edge.
+		arc_distance(a, b, self.center) <= self.angle
+			|| arc_distance(b, c, self.center) <= self.angle
+			|| arc_distance(c, a, self.center) <= self.angle
+	}
+
+	/// Bounding sphere of the cap.
+	pub fn bounding_sphere(&self) -> (Vec3, f32) {
+		if self.angle >= std::f32::consts::FRAC_PI_2 {
+			(Vec3::zero(), self.max_radius)
+		} else {
+			let base = self.min_radius * libm::cosf(self.angle);
+			let center = self.center * (base + self.max_radius) / 2.0;
+			let top = self.center * self.max_radius;
+			let rim = self.max_radius * libm::sinf(self.angle);
+			let radius = (top - center).length().max(libm::hypotf((self.center * base - center).length(), rim));
+			(center, radius)
+		}
+	}
+
+	/// Conservative ray intersection test.
+	///
+	/// If the ray (whose direction is normalized) may cross the cap, return the distance along the ray
+	/// at which it enters the `max_radius` sphere: a lower bound of the distance at which it enters the cap.
+	pub fn intersects_ray(&self, origin: Vec3, dir: Vec3) -> Option<f32> {
+		let dir = dir.normalize();
+		let (t0, t1) = sphere_intersection(origin, dir, self.max_radius)?;
+		let p0 = origin + dir * t0;
+		let p1 = origin + dir * t1;
+
+		if self.contains(p0) || self.contains(p1) {
+			return Some(t0)
+		}
+
+		let n = p0.cross(p1);
+		if n.length_squared() <= 1e-12 * p0.length_squared() * p1.length_squared() {
+			// The ray goes through the center of the sphere.
+			if p0.dot(p1) < 0.0 && (self.contains(dir) || self.contains(-dir)) {
+				return Some(t0)
+			} else {
+				return None
+			}
+		}
+
+		if arc_distance(p0, p1, self.center) <= self.angle {
+			Some(t0)
+		} else {
+			None
+		}
+	}
+}
+
+/// Oriented bounding box.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Obb {
+	pub center: Vec3,
+
+	/// Orthonormal axes of the box.
+	pub axes: [Vec3; 3],
+
+	/// Half size of the box along each axis.
+	pub half_extents: Vec3
+}
+
+impl Obb {
+	/// Smallest box with the given axes containing the given points.
+	pub fn from_points(axes: [Vec3; 3], points: &[Vec3]) -> Obb {
+		let mut min = Vec3::splat(f32::INFINITY);
+		let mut max = Vec3::splat(f32::NEG_INFINITY);
+		for p in points {
+			let local = Vec3::new(p.dot(axes[0]), p.dot(axes[1]), p.dot(axes[2]));
+			min = min.min(local);
+			max = max.max(local);
+		}
+
+		let local_center = (min + max) / 2.0;
+		Obb {
+			center: axes[0] * local_center.x + axes[1] * local_center.y + axes[2] * local_center.z,
+			axes,
+			half_extents: (max - min) / 2.0
+		}
+	}
+
+	/// Bounding box of a surface region (spherical triangle), between the given radii.
+	///
+	/// The box is aligned with the local tangent frame at the region center,
+	/// and also contains the flat triangles approximating the region.
+	pub fn from_region(corners: [Vec3; 3], min_radius: f32, max_radius: f32) -> Obb {
+		let [a, b, c] = corners;
+		let up = (a + b + c).normalize();
+		let frame = crate::location::Frame::new(up);
+
+		let mut points = Vec::new();
+		let mut max_angle = 0.0f32;
+		for (p, q) in [(a, b), (b, c), (c, a)].iter() {
+			let p = p.normalize();
+			let m = (p + q.normalize()).normalize();
+			max_angle = max_angle.max(angle(p, m));
+			for v in [p, m].iter() {
+				points.push(*v * max_radius);
+				points.push(*v * min_radius);
+			}
+		}
+		points.push(up * max_radius);
+
+		// The flat triangles dip under the `min_radius` sphere.
+		let dip = min_radius * (1.0 - libm::cosf(angle(up, a).max(angle(up, b)).max(angle(up, c))));
+		points.push(up * (min_radius - dip));
+
+		// Arcs bulge out of the chords between the sampled points.
+		let bulge = max_radius * (1.0 - libm::cosf(max_angle / 2.0));
+
+		let mut obb = Obb::from_points([frame.east, frame.north, frame.up], &points);
+		obb.half_extents += Vec3::splat(bulge);
+		obb
+	}
+
+	/// Checks if the box contains the given point.
+	pub fn contains(&self, p: Vec3) -> bool {
+		let d = p - self.center;
+		(0..3).all(|i| d.dot(self.axes[i]).abs() <= self.half_extents[i])
+	}
+
+	/// Ray intersection (slab test).
+	///
+	/// Return the distance along the ray (whose direction is normalized)
+	/// at which it enters the box, if it does.
+	pub fn intersects_ray(&self, origin: Vec3, dir: Vec3) -> Option<f32> {
+		let dir = dir.normalize();
+		let d = self.center - origin;
+		let mut t_min = 0.0f32;
+		let mut t_max = f32::INFINITY;
+
+		for i in 0..3 {
+			let e = self.axes[i].dot(d);
+			let f = self.axes[i].dot(dir);
+			let h = self.half_extents[i];
+			if f.abs() > 1e-12 {
+				let mut t1 = (e - h) / f;
+				let mut t2 = (e + h) / f;
+				if t1 > t2 {
+					std::mem::swap(&mut t1, &mut t2)
+				}
+
+				t_min = t_min.max(t1);
+				t_max = t_max.min(t2);
+				if t_min > t_max {
+					return None
+				}
+			} else if e.abs() > h {
+				return None
+			}
+		}
+
+		Some(t_min)
+	}
+}
+
+/// Angle between two directions.
+pub(crate) fn angle(a: Vec3, b: Vec3) -> f32 {
+	libm::atan2f(a.cross(b).length(), a.dot(b))
+}
+
+/// Angular distance between a direction and the great-circle arc `ab`.
+pub(crate) fn arc_distance(a: Vec3, b: Vec3, p: Vec3) -> f32 {
+	let n = a.cross(b);
+	if n.length_squared() > 1e-12 * a.length_squared() * b.length_squared() {
+		let n = n.normalize();
+		let q = p - n * n.dot(p);
+		if a.cross(q).dot(n) >= 0.0 && q.cross(b).dot(n) >= 0.0 {
+			return libm::asinf(n.dot(p.normalize()).abs().min(1.0))
+		}
+	}
+
+	angle(a, p).min(angle(b, p))
+}
+
+/// Ray-sphere intersection (centered on the origin).
+///
+/// Return the clipped (positive) distances range along the ray inside the sphere.
+pub(crate) fn sphere_intersection(origin: Vec3, dir: Vec3, radius: f32) -> Option<(f32, f32)> {
+	let b = origin.dot(dir);
+	let c = origin.length_squared() - radius * radius;
+	let delta = b * b - c;
+	if delta < 0.0 {
+		return None
+	}
+
+	let s = delta.sqrt();
+	let t0 = (-b - s).max(0.0);
+	let t1 = -b + s;
+	if t1 < 0.0 {
+		None
+	} else {
+		Some((t0, t1))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use glam::Vec3;
+	use super::{Cap, Obb};
+	use crate::topology::Cell;
+
+	#[test]
+	fn cell_bounds() {
+		for cell in Cell::all(2) {
+			let cap = cell.cap(0.9, 1.1);
+			let obb = cell.obb(0.9, 1.1);
+			let [a, b, c] = cell.corners();
+			for (u, v, w) in [(1.0, 0.0, 0.0), (0.3, 0.3, 0.4), (0.5, 0.5, 0.0), (0.1, 0.0, 0.9)].iter() {
+				let p = (a * *u + b * *v + c * *w).normalize();
+				for r in [0.901, 1.0, 1.099].iter() {
+					assert!(cap.contains_point(p * *r));
+					assert!(obb.contains(p * *r))
+				}
+
+				// Flat triangle.
+				assert!(obb.contains((a * *u + b * *v + c * *w) * 0.9))
+			}
+		}
+	}
+
+	#[test]
+	fn rays() {
+		let cap = Cap::new(Vec3::unit_x(), 0.1, 0.9, 1.1);
+		assert!(cap.intersects_ray(Vec3::new(3.0, 0.0, 0.0), -Vec3::unit_x()).is_some());
+		assert!(cap.intersects_ray(Vec3::new(3.0, 0.0, 0.5), -Vec3::unit_x()).is_none());
+		assert!(cap.intersects_ray(Vec3::new(1.0, -2.0, 0.0), Vec3::unit_y()).is_some());
+		assert!(cap.intersects_ray(Vec3::new(-3.0, 0.0, 0.0), Vec3::unit_x()).is_some());
+
+		let obb = Cell::locate(Vec3::unit_x(), 3).obb(0.9, 1.1);
+		assert!(obb.intersects_ray(Vec3::new(3.0, 0.0, 0.0), -Vec3::unit_x()).is_some());
+		assert!(obb.intersects_ray(Vec3::new(3.0, 0.0, 0.5), -Vec3::unit_x()).is_none());
+		assert!(Obb::from_points([Vec3::unit_x(), Vec3::unit_y(), Vec3::unit_z()], &[Vec3::zero(), Vec3::one()]).contains(Vec3::splat(0.5)))
+	}
+
+	#[test]
+	fn regions() {
+		for cell in Cell::all(1) {
+			let corners = cell.corners();
+			let center = corners[0] + corners[1] + corners[2];
+			assert!(Cap::new(center, 0.01, 0.9, 1.1).intersects_region(corners, 1.0, 1.0));
+			assert!(!Cap::new(-center, 0.01, 0.9, 1.1).intersects_region(corners, 1.0, 1.0));
+			assert!(!Cap::new(center, 0.01, 1.2, 1.3).intersects_region(corners, 1.0, 1.0));
+		}
+	}
+}
@@ -0,0 +1,299 @@
+use glam::Vec3;
+use super::sphere::{icosahedron_vertices, ICOSAHEDRON_FACES};
+use super::bounds::{Cap, Obb};
+use super::Vertex;
+
+/// Triangular cell of the icosahedral subdivision.
+///
+/// At depth 0, the 20 cells are the faces of the icosahedron,
+/// in the same order as the regions of [`Sphere`](super::Sphere).
+/// Each cell of index `i` is then subdivided into the 4 cells
+/// of index `4*i`, `4*i + 1`, `4*i + 2` and `4*i + 3` at the next depth:
+///
+/// ```text
+///       a
+///     /   \
+///    e --- f
+///  /   \ /   \
+/// c --- d --- b
+/// ```
+///
+/// respectively `(a, f, e)`, `(f, b, d)`, `(e, d, c)` and `(d, e, f)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Cell {
+	depth: u32,
+	index: u32
+}
+
+/// Maximum depth of a cell, so that its index fits in a `u32`.
+pub const MAX_DEPTH: u32 = 13;
+
+impl Cell {
+	pub fn new(depth: u32, index: u32) -> Cell {
+		debug_assert!(depth <= MAX_DEPTH && index < Cell::count(depth));
+		Cell {
+			depth,
+			index
+		}
+	}
+
+	/// Number of cells at the given depth.
+	pub const fn count(depth: u32) -> u32 {
+		20 * (1 << (2 * depth))
+	}
+
+	/// Iterator over all the cells of the given depth.
+	pub fn all(depth: u32) -> impl Iterator<Item = Cell> {
+		(0..Cell::count(depth)).map(move |index| Cell::new(depth, index))
+	}
+
+	pub fn depth(&self) -> u32 {
+		self.depth
+	}
+
+	pub fn index(&self) -> u32 {
+		self.index
+	}
+
+	/// Unique identifier of the cell among the cells of every depth.
+	pub fn id(&self) -> u64 {
+		let offset = 20 * (((1u64 << (2 * self.depth)) - 1) / 3);
+		offset + self.index as u64
+	}
+
+	pub fn parent(&self) -> Option<Cell> {
+		if self.depth == 0 {
+			None
+		} else {
+			Some(Cell::new(self.depth - 1, self.index / 4))
+		}
+	}
+
+	/// Ancestor of the cell at the given depth (which must not be greater than the cell depth).
+	pub fn ancestor(&self, depth: u32) -> Cell {
+		Cell::new(depth, self.index >> (2 * (self.depth - depth)))
+	}
+
+	pub fn children(&self) -> [Cell; 4] {
+		let i = self.index * 4;
+		let d = self.depth + 1;
+		[
+			Cell::new(d, i),
+			Cell::new(d, i + 1),
+			Cell::new(d, i + 2),
+			Cell::new(d, i + 3)
+		]
+	}
+
+	/// Descendants of this cell at the given depth (which must not be lower than the cell depth).
+	pub fn descendants(&self, depth: u32) -> impl Iterator<Item = Cell> {
+		let shift = 2 * (depth - self.depth);
+		let start = self.index << shift;
+		(start..(start + (1 << shift))).map(move |index| Cell::new(depth, index))
+	}
+
+	/// Corners of the cell, on the unit sphere.
+	pub fn corners(&self) -> [Vec3; 3] {
+		let v = icosahedron_vertices();
+		let face = ICOSAHEDRON_FACES[(self.index >> (2 * self.depth)) as usize];
+		let mut corners = [v[face[0]], v[face[1]], v[face[2]]];
+
+		for level in (0..self.depth).rev() {
+			corners = subdivide(corners)[((self.index >> (2 * level)) & 3) as usize];
+		}
+
+		corners
+	}
+
+	/// Vertices of the subdivision at the cell depth, in the same order as the corners.
+	pub fn vertices(&self) -> [Vertex; 3] {
+		let (face, lattice) = self.lattice();
+		[
+			Vertex::from_lattice(self.depth, face, lattice[0]),
+			Vertex::from_lattice(self.depth, face, lattice[1]),
+			Vertex::from_lattice(self.depth, face, lattice[2])
+		]
+	}
+
+	/// Icosahedron face of the cell, and the lattice coordinates of its corners in this face.
+	///
+	/// Coordinates `(i, j)` are the weights of the second and third corners of the face,
+	/// on a scale of `2^depth`.
+	pub(crate) fn lattice(&self) -> (u32, [(u32, u32); 3]) {
+		let n = 1 << self.depth;
+		let mut corners = [(0, 0), (n, 0), (0, n)];
+		for level in (0..self.depth).rev() {
+			let [a, b, c] = corners;
+			let mid = |p: (u32, u32), q: (u32, u32)| ((p.0 + q.0) / 2, (p.1 + q.1) / 2);
+			let (d, e, f) = (mid(b, c), mid(c, a), mid(a, b));
+			corners = match (self.index >> (2 * level)) & 3 {
+				0 => [a, f, e],
+				1 => [f, b, d],
+				2 => [e, d, c],
+				_ => [d, e, f]
+			}
+		}
+
+		(self.index >> (2 * self.depth), corners)
+	}
+
+	/// Center of the cell, on the unit sphere.
+	pub fn center(&self) -> Vec3 {
+		let [a, b, c] = self.corners();
+		(a + b + c).normalize()
+	}
+
+	/// Area of the cell on the unit sphere (solid angle), in steradians.
+	pub fn area(&self) -> f32 {
+		let [a, b, c] = self.corners();
+		2.0 * libm::atan2f(a.dot(b.cross(c)).abs(), 1.0 + a.dot(b) + b.dot(c) + c.dot(a))
+	}
+
+	/// Barycentric coordinates of the given direction, projected on the plane of the corners.
+	pub fn barycentric(&self, dir: Vec3) -> [f32; 3] {
+		let [a, b, c] = self.corners();
+		let n = (b - a).cross(c - a);
+		let p = dir * (n.dot(a) / n.dot(dir));
+		let area = n.length_squared();
+		let u = (c - b).cross(p - b).dot(n) / area;
+		let v = (a - c).cross(p - c).dot(n) / area;
+		[u, v, 1.0 - u - v]
+	}
+
+	/// Cells of the same depth sharing an edge with this cell.
+	///
+	/// The `i`-th neighbor is across the edge opposite to the `i`-th corner.
+	pub fn neighbors(&self) -> [Cell; 3] {
+		let corners = self.corners();
+		let mut neighbors = [*self; 3];
+		for (i, n) in neighbors.iter_mut().enumerate() {
+			let c = corners[i];
+			let m = (corners[(i + 1) % 3] + corners[(i + 2) % 3]) / 2.0;
+			*n = Cell::locate(m + (m - c) * 0.01, self.depth)
+		}
+
+		neighbors
+	}
+
+	/// Bounding cap of the cell surface, displaced between the given radii.
+	///
+	/// The cap also contains the flat triangle joining the corners of the cell.
+	pub fn cap(&self, min_radius: f32, max_radius: f32) -> Cap {
+		Cap::from_region(self.corners(), min_radius, max_radius)
+	}
+
+	/// Oriented bounding box of the cell surface, displaced between the given radii.
+	pub fn obb(&self, min_radius: f32, max_radius: f32) -> Obb {
+		Obb::from_region(self.corners(), min_radius, max_radius)
+	}
+
+	/// Checks if the given direction points inside the cell.
+	pub fn contains(&self, dir: Vec3) -> bool {
+		inclusion(self.corners(), dir) >= -1e-6
+	}
+
+	/// Find the cell of the given depth containing the given direction.
+	pub fn locate(dir: Vec3, depth: u32) -> Cell {
+		let dir = dir.normalize();
+		let v = icosahedron_vertices();
+
+		let mut best = 0;
+		let mut best_score = f32::NEG_INFINITY;
+		for (i, face) in ICOSAHEDRON_FACES.iter().enumerate() {
+			let score = inclusion([v[face[0]], v[face[1]], v[face[2]]], dir);
+			if score > best_score {
+				best = i;
+				best_score = score;
+			}
+		}
+
+		let face = ICOSAHEDRON_FACES[best];
+		let mut corners = [v[face[0]], v[face[1]], v[face[2]]];
+		let mut index = best as u32;
+		for _ in 0..depth {
+			let children = subdivide(corners);
+			let mut best = 0;
+			let mut best_score = f32::NEG_INFINITY;
+			for (i, child) in children.iter().enumerate() {
+				let score = inclusion(*child, dir);
+				if score > best_score {
+					best = i;
+					best_score = score;
+				}
+			}
+
+			corners = children[best];
+			index = index * 4 + best as u32;
+		}
+
+		Cell::new(depth, index)
+	}
+}
+
+/// Subdivide a spherical triangle into its 4 children.
+pub(crate) fn subdivide([a, b, c]: [Vec3; 3]) -> [[Vec3; 3]; 4] {
+	let d = ((b + c) / 2.0).normalize();
+	let e = ((c + a) / 2.0).normalize();
+	let f = ((a + b) / 2.0).normalize();
+
+	[
+		[a, f, e],
+		[f, b, d],
+		[e, d, c],
+		[d, e, f]
+	]
+}
+
+/// Signed inclusion score of a direction in a spherical triangle.
+///
+/// It is positive inside, null on the border and negative outside.
+fn inclusion([a, b, c]: [Vec3; 3], dir: Vec3) -> f32 {
+	let ab = a.cross(b).normalize().dot(dir);
+	let bc = b.cross(c).normalize().dot(dir);
+	let ca = c.cross(a).normalize().dot(dir);
+	ab.min(bc).min(ca)
+}
+
+#[cfg(test)]
+mod test {
+	use super::{Cell, MAX_DEPTH};
+
+	#[test]
+	fn locate() {
+		for cell in Cell::all(3) {
+			assert_eq!(Cell::locate(cell.center(), 3), cell);
+			assert_eq!(cell.ancestor(1), Cell::locate(cell.center(), 1));
+		}
+	}
+
+	#[test]
+	fn ids() {
+		let mut ids: Vec<u64> = (0..4).flat_map(Cell::all).map(|c| c.id()).collect();
+		let len = ids.len();
+		ids.sort();
+		ids.dedup();
+		assert_eq!(ids.len(), len);
+		assert_eq!(ids[len - 1] as usize, len - 1);
+	}
+
+	#[test]
+	fn neighbors() {
+		let mut area = 0.0;
+		for cell in Cell::all(3) {
+			area += cell.area();
+			for n in cell.neighbors().iter() {
+				assert_ne!(*n, cell);
+				assert!(n.neighbors().contains(&cell))
+			}
+		}
+
+		assert!((area - 4.0 * std::f32::consts::PI).abs() < 1e-3)
+	}
+
+	#[test]
+	fn count() {
+		assert_eq!(Cell::count(MAX_DEPTH) as u64, 20 << (2 * MAX_DEPTH as u64));
+		let last = Cell::new(MAX_DEPTH, Cell::count(MAX_DEPTH) - 1);
+		assert_eq!(last.id() + 1, (0..=MAX_DEPTH).map(|d| Cell::count(d) as u64).sum::<u64>());
+	}
+}
@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+use glam::Vec3;
+use super::Cell;
+
+/// Icosphere vertex graph at a given depth.
+///
+/// The faces are the cells of the depth (or of a region), indexed by cell index.
+/// Vertices shared by neighbor cells are merged.
+pub struct Mesh {
+	depth: u32,
+
+	/// Region of the mesh (`None` for the whole sphere).
+	root: Option<Cell>,
+
+	/// Cell index of the first face.
+	first: u32,
+
+	/// Vertices on the unit sphere.
+	vertices: Vec<Vec3>,
+
+	/// Vertices of each face.
+	faces: Vec<[u32; 3]>,
+
+	/// Vertices sharing an edge with each vertex.
+	adjacency: Vec<Vec<u32>>,
+
+	/// Faces around each vertex.
+	vertex_faces: Vec<Vec<u32>>,
+
+	/// Vertices on the border of the mesh (for regions).
+	boundary: Vec<bool>
+}
+
+impl Mesh {
+	/// Mesh of the whole sphere.
+	pub fn new(depth: u32) -> Mesh {
+		Mesh::from_cells(depth, None, 0, Cell::all(depth))
+	}
+
+	/// Mesh of the given region, made of its descendants at the given depth.
+	pub fn region(region: Cell, depth: u32) -> Mesh {
+		let first = region.descendants(depth).next().unwrap().index();
+		Mesh::from_cells(depth, Some(region), first, region.descendants(depth))
+	}
+
+	fn from_cells<I>(depth: u32, root: Option<Cell>, first: u32, cells: I) -> Mesh where I: Iterator<Item = Cell> {
+		let mut vertices = Vec::new();
+		let mut indexes = HashMap::new();
+		let mut faces = Vec::new();
+
+		// Shared corners of neighbor cells are computed identically.
+		for cell in cells {
+			let mut face = [0; 3];
+			for (i, v) in cell.corners().iter().enumerate() {
+				let key = [v.x.to_bits(), v.y.to_bits(), v.z.to_bits()];
+				face[i] = *indexes.entry(key).or_insert_with(|| {
+					vertices.push(*v);
+					(vertices.len() - 1) as u32
+				})
+			}
+
+			faces.push(face)
+		}
+
+		let mut adjacency = vec![Vec::with_capacity(6); vertices.len()];
+		let mut vertex_faces = vec![Vec::with_capacity(6); vertices.len()];
+		for (f, face) in faces.iter().enumerate() {
+			for i in 0..3 {
+				let (a, b) = (face[i], face[(i + 1) % 3]);
+				if !adjacency[a as usize].contains(&b) {
+					adjacency[a as usize].push(b);
+					adjacency[b as usize].push(a)
+				}
+
+				vertex_faces[a as usize].push(f as u32 + first)
+			}
+		}
+
+		// Inner vertices have as many faces as neighbors.
+		let boundary = (0..vertices.len()).map(|v| vertex_faces[v].len() < adjacency[v].len()).collect();
+
+		Mesh {
+			depth,
+			root,
+			first,
+			vertices,
+			faces,
+			adjacency,
+			vertex_faces,
+			boundary
+		}
+	}
+
+	pub fn depth(&self) -> u32 {
+		self.depth
+	}
+
+	/// Region of the mesh (`None` for the whole sphere).
+	pub fn root(&self) -> Option<Cell> {
+		self.root
+	}
+
+	pub fn vertices(&self) -> &[Vec3] {
+		&self.vertices
+	}
+
+	pub fn vertex(&self, index: u32) -> Vec3 {
+		self.vertices[index as usize]
+	}
+
+	pub fn faces(&self) -> &[[u32; 3]] {
+		&self.faces
+	}
+
+	/// Checks if the given cell (of the mesh depth) is a face of the mesh.
+	pub fn contains(&self, cell: Cell) -> bool {
+		cell.depth() == self.depth && cell.index() >= self.first && ((cell.index() - self.first) as usize) < self.faces.len()
+	}
+
+	/// Vertices of the given cell (which must be a face of the mesh).
+	pub fn face(&self, cell: Cell) -> [u32; 3] {
+		self.faces[(cell.index() - self.first) as usize]
+	}
+
+	/// Checks if the given vertex is on the border of the mesh.
+	pub fn is_boundary(&self, vertex: u32) -> bool {
+		self.boundary[vertex as usize]
+	}
+
+	/// Interpolate values defined on the vertices, in the given direction.
+	///
+	/// Returns `None` if the direction is outside of the mesh.
+	pub fn interpolate(&self, values: &[f32], dir: Vec3) -> Option<f32> {
+		let cell = Cell::locate(dir, self.depth);
+		if !self.contains(cell) {
+			return None
+		}
+
+		let [a, b, c] = self.face(cell);
+		let [u, v, w] = cell.barycentric(dir);
+		Some(values[a as usize] * u + values[b as usize] * v + values[c as usize] * w)
+	}
+
+	/// Vertices sharing an edge with the given vertex.
+	pub fn neighbors(&self, vertex: u32) -> &[u32] {
+		&self.adjacency[vertex as usize]
+	}
+
+	/// Faces (cell indexes) of the mesh around the given vertex.
+	pub fn vertex_faces(&self, vertex: u32) -> &[u32] {
+		&self.vertex_faces[vertex as usize]
+	}
+
+	/// Vertex closest to the given direction (which must be inside the mesh).
+	pub fn nearest(&self, dir: Vec3) -> u32 {
+		let dir = dir.normalize();
+		let face = self.face(Cell::locate(dir, self.depth));
+		let mut best = face[0];
+		for v in face.iter() {
+			if self.vertex(*v).dot(dir) > self.vertex(best).dot(dir) {
+				best = *v
+			}
+		}
+
+		best
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::topology::{Cell, Vertex};
+	use super::Mesh;
+
+	#[test]
+	fn counts() {
+		for depth in 0..4 {
+			let mesh = Mesh::new(depth);
+			assert_eq!(mesh.vertices().len(), Vertex::count(depth) as usize);
+			let valences: Vec<usize> = (0..mesh.vertices().len() as u32).map(|v| mesh.neighbors(v).len()).collect();
+			assert_eq!(valences.iter().filter(|v| **v == 5).count(), 12);
+			assert!(valences.iter().all(|v| *v == 5 || *v == 6));
+			assert!((0..mesh.vertices().len() as u32).all(|v| !mesh.is_boundary(v)))
+		}
+	}
+
+	#[test]
+	fn region() {
+		let region = Cell::new(1, 7);
+		let mesh = Mesh::region(region, 4);
+		assert_eq!(mesh.faces().len(), 64);
+		assert_eq!((0..mesh.vertices().len() as u32).filter(|v| mesh.is_boundary(*v)).count(), 24);
+
+		let values: Vec<f32> = mesh.vertices().iter().map(|v| v.x).collect();
+		let inside = region.center();
+		assert!((mesh.interpolate(&values, inside).unwrap() - inside.x).abs() < 1e-2);
+		assert!(mesh.interpolate(&values, -inside).is_none())
+	}
+}
@@ -5,5 +5,15 @@
 
 pub mod utils;
 pub mod sphere;
+pub mod cell;
+pub mod bounds;
+pub mod mesh;
+pub mod vertex;
+pub mod storage;
 
 pub use sphere::Sphere;
+pub use cell::Cell;
+pub use bounds::{Cap, Obb};
+pub use mesh::Mesh;
+pub use vertex::Vertex;
+pub use storage::{Storage, Element, Reduction};
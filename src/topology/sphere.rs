@@ -1,4 +1,6 @@
+use std::convert::TryInto;
 use glam::Vec3;
+use super::{Cell, bounds::{Cap, Obb}};
 use geometer::{
 	Geometry,
 	geometry,
@@ -35,59 +37,86 @@ pub struct Sphere {
 	pub regions: [Geometry<Vertex>; 20]
 }
 
+/// Vertices of the icosahedron, normalized.
+pub(crate) fn icosahedron_vertices() -> [Vec3; 12] {
+	let t = (1.0 + 5.0f32.sqrt()) / 2.0;
+
+	[
+		Vec3::new(-1.0,  t  ,  0.0).normalize(),
+		Vec3::new( 1.0,  t  ,  0.0).normalize(),
+		Vec3::new(-1.0, -t  ,  0.0).normalize(),
+		Vec3::new( 1.0, -t  ,  0.0).normalize(),
+		Vec3::new( 0.0, -1.0,  t  ).normalize(),
+		Vec3::new( 0.0,  1.0,  t  ).normalize(),
+		Vec3::new( 0.0, -1.0, -t  ).normalize(),
+		Vec3::new( 0.0,  1.0, -t  ).normalize(),
+		Vec3::new( t  ,  0.0, -1.0).normalize(),
+		Vec3::new( t  ,  0.0,  1.0).normalize(),
+		Vec3::new(-t  ,  0.0, -1.0).normalize(),
+		Vec3::new(-t  ,  0.0,  1.0).normalize()
+	]
+}
+
+/// Faces of the icosahedron, as indexes in the vertices array.
+pub(crate) const ICOSAHEDRON_FACES: [[usize; 3]; 20] = [
+	// 5 faces around point 0
+	[0, 11, 5],
+	[0, 5, 1],
+	[0, 1, 7],
+	[0, 7, 10],
+	[0, 10, 11],
+
+	// 5 adjacent faces
+	[1, 5, 9],
+	[5, 11, 4],
+	[11, 10, 2],
+	[10, 7, 6],
+	[7, 1, 8],
+
+	// 5 faces around point 3
+	[3, 9, 4],
+	[3, 4, 2],
+	[3, 2, 6],
+	[3, 6, 8],
+	[3, 8, 9],
+
+	// 5 adjacent faces
+	[4, 9, 5],
+	[2, 4, 11],
+	[6, 2, 10],
+	[8, 6, 7],
+	[9, 8, 1]
+];
+
 impl Sphere {
 	pub fn new() -> Sphere {
-		let t = (1.0 + 5.0f32.sqrt()) / 2.0;
-
-		let v = [
-			Vec3::new(-1.0,  t  ,  0.0),
-			Vec3::new( 1.0,  t  ,  0.0),
-			Vec3::new(-1.0, -t  ,  0.0),
-			Vec3::new( 1.0, -t  ,  0.0),
-			Vec3::new( 0.0, -1.0,  t  ),
-			Vec3::new( 0.0,  1.0,  t  ),
-			Vec3::new( 0.0, -1.0, -t  ),
-			Vec3::new( 0.0,  1.0, -t  ),
-			Vec3::new( t  ,  0.0, -1.0),
-			Vec3::new( t  ,  0.0,  1.0),
-			Vec3::new(-t  ,  0.0, -1.0),
-			Vec3::new(-t  ,  0.0,  1.0)
-		];
+		let v = icosahedron_vertices();
+		let regions: Vec<Geometry<Vertex>> = ICOSAHEDRON_FACES.iter().map(|[a, b, c]| new_region(v[*a], v[*b], v[*c])).collect();
 
 		Sphere {
-			regions: [
-				// 5 faces around point 0
-				new_region(v[0], v[11], v[5]),
-				new_region(v[0], v[5], v[1]),
-				new_region(v[0], v[1], v[7]),
-				new_region(v[0], v[7], v[10]),
-				new_region(v[0], v[10], v[11]),
-
-				// 5 adjacent faces
-				new_region(v[1], v[5], v[9]),
-				new_region(v[5], v[11], v[4]),
-				new_region(v[11], v[10], v[2]),
-				new_region(v[10], v[7], v[6]),
-				new_region(v[7], v[1], v[8]),
-
-				// 5 faces around point 3
-				new_region(v[3], v[9], v[4]),
-				new_region(v[3], v[4], v[2]),
-				new_region(v[3], v[2], v[6]),
-				new_region(v[3], v[6], v[8]),
-				new_region(v[3], v[8], v[9]),
-
-				// 5 adjacent faces
-				new_region(v[4], v[9], v[5]),
-				new_region(v[2], v[4], v[11]),
-				new_region(v[6], v[2], v[10]),
-				new_region(v[8], v[6], v[7]),
-				new_region(v[9], v[8], v[1])
-			]
+			regions: regions.try_into().ok().expect("an icosahedron has 20 faces")
 		}
 	}
 }
 
+impl Default for Sphere {
+	fn default() -> Sphere {
+		Sphere::new()
+	}
+}
+
+impl Sphere {
+	/// Bounding cap of the given face, displaced between the given radii.
+	pub fn cap(&self, face: usize, min_radius: f32, max_radius: f32) -> Cap {
+		Cell::new(0, face as u32).cap(min_radius, max_radius)
+	}
+
+	/// Oriented bounding box of the given face, displaced between the given radii.
+	pub fn obb(&self, face: usize, min_radius: f32, max_radius: f32) -> Obb {
+		Cell::new(0, face as u32).obb(min_radius, max_radius)
+	}
+}
+
 fn new_region(a: Vec3, b: Vec3, c: Vec3) -> Geometry<Vertex> {
 	let mut vertices = Vec::new();
 	vertices.resize(vertex_count(MAX_PRECISION), vertex::Position3::new(0.0, 0.0, 0.0));
@@ -0,0 +1,450 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::{Add, Mul};
+use glam::Vec3;
+use super::{Cell, Vertex, Mesh};
+
+/// Elements of the subdivision holding the values of a storage.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Element {
+	Vertex,
+	Face
+}
+
+/// Reduction of the values covered by a coarser element, when downsampling.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reduction {
+	Mean,
+	Min,
+	Max
+}
+
+#[derive(Clone, Debug)]
+enum Values<T> {
+	/// Values of all the elements, by index.
+	Dense(Vec<T>),
+
+	/// Values of the elements of some regions, by id.
+	Sparse(Vec<Cell>, HashMap<u64, T>)
+}
+
+/// Values stored on the vertices or faces of the icosahedral subdivision at a given depth.
+///
+/// A storage is either dense, covering the whole sphere,
+/// or sparse, covering only the regions (cells) filled so far.
+/// Vertex values are interpolated inside the faces, face values are constant.
+#[derive(Clone, Debug)]
+pub struct Storage<T> {
+	element: Element,
+	depth: u32,
+	values: Values<T>
+}
+
+impl<T: Clone> Storage<T> {
+	/// Dense storage, with the same value for every element.
+	pub fn dense(element: Element, depth: u32, value: T) -> Storage<T> {
+		let count = match element {
+			Element::Vertex => Vertex::count(depth),
+			Element::Face => Cell::count(depth)
+		};
+
+		Storage {
+			element,
+			depth,
+			values: Values::Dense(vec![value; count as usize])
+		}
+	}
+
+	/// Dense storage, with values computed from the positions of the elements
+	/// (vertex positions or face centers).
+	pub fn from_fn<F>(element: Element, depth: u32, f: F) -> Storage<T> where F: FnMut(Vec3) -> T {
+		let mut storage = Storage::sparse(element, depth);
+		storage.fill(Cell::all(0), f);
+		storage.into_dense()
+	}
+
+	/// Dense storage from the values of all the elements, by index.
+	pub fn from_values(element: Element, depth: u32, values: Vec<T>) -> Storage<T> {
+		debug_assert_eq!(values.len() as u32, match element {
+			Element::Vertex => Vertex::count(depth),
+			Element::Face => Cell::count(depth)
+		});
+
+		Storage {
+			element,
+			depth,
+			values: Values::Dense(values)
+		}
+	}
+
+	/// Vertex storage of the values of the vertices of a mesh.
+	///
+	/// The storage is sparse for the mesh of a region.
+	pub fn from_mesh(mesh: &Mesh, values: &[T]) -> Storage<T> {
+		let depth = mesh.depth();
+		let regions: Vec<Cell> = match mesh.root() {
+			Some(region) => vec![region],
+			None => Cell::all(0).collect()
+		};
+
+		let mut storage = Storage {
+			element: Element::Vertex,
+			depth,
+			values: Values::Sparse(regions.clone(), HashMap::new())
+		};
+
+		for cell in regions.iter().flat_map(|r| r.descendants(depth)) {
+			for (v, i) in cell.vertices().iter().zip(mesh.face(cell).iter()) {
+				storage.set_vertex(*v, values[*i as usize].clone())
+			}
+		}
+
+		if mesh.root().is_some() {
+			storage
+		} else {
+			storage.into_dense()
+		}
+	}
+
+	/// Empty sparse storage.
+	pub fn sparse(element: Element, depth: u32) -> Storage<T> {
+		Storage {
+			element,
+			depth,
+			values: Values::Sparse(Vec::new(), HashMap::new())
+		}
+	}
+
+	pub fn element(&self) -> Element {
+		self.element
+	}
+
+	pub fn depth(&self) -> u32 {
+		self.depth
+	}
+
+	pub fn is_sparse(&self) -> bool {
+		match self.values {
+			Values::Dense(_) => false,
+			Values::Sparse(..) => true
+		}
+	}
+
+	/// Regions covered by a sparse storage (empty for a dense storage).
+	pub fn regions(&self) -> &[Cell] {
+		match &self.values {
+			Values::Dense(_) => &[],
+			Values::Sparse(regions, _) => regions
+		}
+	}
+
+	/// Number of stored values.
+	pub fn len(&self) -> usize {
+		match &self.values {
+			Values::Dense(values) => values.len(),
+			Values::Sparse(_, values) => values.len()
+		}
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Stored values, in no particular order.
+	pub fn values(&self) -> Box<dyn Iterator<Item = &T> + '_> {
+		match &self.values {
+			Values::Dense(values) => Box::new(values.iter()),
+			Values::Sparse(_, values) => Box::new(values.values())
+		}
+	}
+
+	/// Compute the values of the given region (not deeper than the storage) from the element positions.
+	///
+	/// The region is added to the regions of a sparse storage.
+	pub fn fill_region<F>(&mut self, region: Cell, f: F) where F: FnMut(Vec3) -> T {
+		debug_assert!(region.depth() <= self.depth);
+		if let Values::Sparse(regions, _) = &mut self.values {
+			if !regions.contains(&region) {
+				regions.push(region)
+			}
+		}
+
+		self.fill(std::iter::once(region), f)
+	}
+
+	fn fill<I, F>(&mut self, regions: I, mut f: F) where I: Iterator<Item = Cell>, F: FnMut(Vec3) -> T {
+		let depth = self.depth;
+		for cell in regions.flat_map(|r| r.descendants(depth)) {
+			match self.element {
+				Element::Face => self.set_face(cell, f(cell.center())),
+				Element::Vertex => {
+					for (v, p) in cell.vertices().iter().zip(cell.corners().iter()) {
+						if self.vertex(*v).is_none() {
+							self.set_vertex(*v, f(*p))
+						}
+					}
+				}
+			}
+		}
+	}
+
+	/// Value of the face containing the given cell (or the cell center, for a coarser cell).
+	pub fn face(&self, cell: Cell) -> Option<&T> {
+		debug_assert_eq!(self.element, Element::Face);
+		let cell = if cell.depth() >= self.depth {
+			cell.ancestor(self.depth)
+		} else {
+			Cell::locate(cell.center(), self.depth)
+		};
+
+		match &self.values {
+			Values::Dense(values) => values.get(cell.index() as usize),
+			Values::Sparse(_, values) => values.get(&cell.id())
+		}
+	}
+
+	/// Value of the given vertex (of the storage depth).
+	pub fn vertex(&self, vertex: Vertex) -> Option<&T> {
+		debug_assert!(self.element == Element::Vertex && vertex.depth() == self.depth);
+		match &self.values {
+			Values::Dense(values) => values.get(vertex.index() as usize),
+			Values::Sparse(_, values) => values.get(&vertex.id())
+		}
+	}
+
+	/// Set the value of the given face (of the storage depth).
+	pub fn set_face(&mut self, cell: Cell, value: T) {
+		debug_assert!(self.element == Element::Face && cell.depth() == self.depth);
+		match &mut self.values {
+			Values::Dense(values) => values[cell.index() as usize] = value,
+			Values::Sparse(_, values) => { values.insert(cell.id(), value); }
+		}
+	}
+
+	/// Set the value of the given vertex (of the storage depth).
+	pub fn set_vertex(&mut self, vertex: Vertex, value: T) {
+		debug_assert!(self.element == Element::Vertex && vertex.depth() == self.depth);
+		match &mut self.values {
+			Values::Dense(values) => values[vertex.index() as usize] = value,
+			Values::Sparse(_, values) => { values.insert(vertex.id(), value); }
+		}
+	}
+
+	/// Cells of the storage depth covered by the storage.
+	fn cells(&self) -> Vec<Cell> {
+		match &self.values {
+			Values::Dense(_) => Cell::all(self.depth).collect(),
+			Values::Sparse(regions, _) => regions.iter().flat_map(|r| r.descendants(self.depth)).collect()
+		}
+	}
+
+	/// Same storage at another depth, with the regions of a sparse storage clamped to this depth.
+	fn resampled(&self, depth: u32) -> Storage<T> {
+		let mut regions: Vec<Cell> = self.regions().iter().map(|r| if r.depth() > depth { r.ancestor(depth) } else { *r }).collect();
+		regions.sort();
+		regions.dedup();
+
+		Storage {
+			element: self.element,
+			depth,
+			values: Values::Sparse(regions, HashMap::new())
+		}
+	}
+
+	/// Convert a storage built sparse to a dense one, if the source was dense.
+	fn like(self, source: &Storage<T>) -> Storage<T> {
+		if source.is_sparse() {
+			self
+		} else {
+			self.into_dense()
+		}
+	}
+
+	/// Dense storage from a storage covering the whole sphere.
+	fn into_dense(self) -> Storage<T> {
+		let values = match self.values {
+			Values::Dense(values) => values,
+			Values::Sparse(_, mut values) => match self.element {
+				Element::Vertex => Vertex::all(self.depth).map(|v| values.remove(&v.id()).unwrap()).collect(),
+				Element::Face => Cell::all(self.depth).map(|c| values.remove(&c.id()).unwrap()).collect()
+			}
+		};
+
+		Storage {
+			element: self.element,
+			depth: self.depth,
+			values: Values::Dense(values)
+		}
+	}
+
+	/// Storage at a coarser depth, reducing the values covered by each element with the given function.
+	///
+	/// A face covers its descendants, a vertex covers the finer vertices closer to it than to the other corners
+	/// of the cells around it.
+	pub fn downsample_with<F>(&self, depth: u32, mut f: F) -> Storage<T> where F: FnMut(&[T]) -> T {
+		debug_assert!(depth <= self.depth);
+		let mut storage = self.resampled(depth);
+		let mut coarse: Vec<Cell> = self.cells().iter().map(|c| c.ancestor(depth)).collect();
+		coarse.dedup();
+
+		match self.element {
+			Element::Face => {
+				for cell in coarse {
+					let values: Vec<T> = cell.descendants(self.depth).filter_map(|c| self.face(c).cloned()).collect();
+					if !values.is_empty() {
+						storage.set_face(cell, f(&values))
+					}
+				}
+			},
+			Element::Vertex => {
+				let m = 1 << (self.depth - depth);
+				let mut covered: HashMap<Vertex, Vec<T>> = HashMap::new();
+				let mut visited = HashSet::new();
+				for cell in coarse {
+					let (face, lattice) = cell.lattice();
+					let vertices = cell.vertices();
+					for u in 0..=m {
+						for w in 0..=(m - u) {
+							let weights = [m - u - w, u, w];
+							let point = (
+								(lattice[0].0 * weights[0] + lattice[1].0 * weights[1] + lattice[2].0 * weights[2]),
+								(lattice[0].1 * weights[0] + lattice[1].1 * weights[1] + lattice[2].1 * weights[2])
+							);
+
+							let fine = Vertex::from_lattice(self.depth, face, point);
+							let value = match self.vertex(fine) {
+								Some(value) => value,
+								None => continue
+							};
+
+							let max = *weights.iter().max().unwrap();
+							for k in 0..3 {
+								if weights[k] == max && visited.insert((vertices[k], fine)) {
+									covered.entry(vertices[k]).or_default().push(value.clone())
+								}
+							}
+						}
+					}
+				}
+
+				for (vertex, values) in covered {
+					storage.set_vertex(vertex, f(&values))
+				}
+			}
+		}
+
+		storage.like(self)
+	}
+}
+
+impl<T> Storage<T> where T: Copy + Add<Output = T> + Mul<f32, Output = T> {
+	/// Value in the given direction, if covered by the storage.
+	pub fn sample(&self, dir: Vec3) -> Option<T> {
+		let cell = Cell::locate(dir, self.depth);
+		match self.element {
+			Element::Face => self.face(cell).copied(),
+			Element::Vertex => {
+				let [a, b, c] = cell.vertices();
+				let [u, v, w] = cell.barycentric(dir);
+				Some(*self.vertex(a)? * u + *self.vertex(b)? * v + *self.vertex(c)? * w)
+			}
+		}
+	}
+
+	/// Storage at a deeper depth.
+	///
+	/// Vertex values are interpolated linearly inside the faces, face values are copied to their descendants.
+	pub fn upsample(&self, depth: u32) -> Storage<T> {
+		debug_assert!(depth >= self.depth);
+		let mut storage = self.resampled(depth);
+		for cell in self.cells() {
+			match self.element {
+				Element::Face => {
+					if let Some(value) = self.face(cell) {
+						for c in cell.descendants(depth) {
+							storage.set_face(c, *value)
+						}
+					}
+				},
+				Element::Vertex => {
+					let vertices = cell.vertices();
+					let values = match (self.vertex(vertices[0]), self.vertex(vertices[1]), self.vertex(vertices[2])) {
+						(Some(a), Some(b), Some(c)) => [*a, *b, *c],
+						_ => continue
+					};
+
+					let m = 1 << (depth - self.depth);
+					let (face, lattice) = cell.lattice();
+					for u in 0..=m {
+						for w in 0..=(m - u) {
+							let weights = [m - u - w, u, w];
+							let point = (
+								(lattice[0].0 * weights[0] + lattice[1].0 * weights[1] + lattice[2].0 * weights[2]),
+								(lattice[0].1 * weights[0] + lattice[1].1 * weights[1] + lattice[2].1 * weights[2])
+							);
+
+							let value = values[0] * (weights[0] as f32 / m as f32) + values[1] * (weights[1] as f32 / m as f32) + values[2] * (weights[2] as f32 / m as f32);
+							storage.set_vertex(Vertex::from_lattice(depth, face, point), value)
+						}
+					}
+				}
+			}
+		}
+
+		storage.like(self)
+	}
+}
+
+impl<T> Storage<T> where T: Copy + Add<Output = T> + Mul<f32, Output = T> + PartialOrd {
+	/// Storage at a coarser depth, reducing the covered values (see [`Storage::downsample_with`]).
+	pub fn downsample(&self, depth: u32, reduction: Reduction) -> Storage<T> {
+		self.downsample_with(depth, |values| {
+			let first = values[0];
+			match reduction {
+				Reduction::Mean => values[1..].iter().fold(first, |a, b| a + *b) * (1.0 / values.len() as f32),
+				Reduction::Min => values[1..].iter().fold(first, |a, b| if *b < a { *b } else { a }),
+				Reduction::Max => values[1..].iter().fold(first, |a, b| if *b > a { *b } else { a })
+			}
+		})
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use glam::Vec3;
+	use crate::topology::{Cell, Vertex};
+	use super::{Storage, Element, Reduction};
+
+	#[test]
+	fn resampling() {
+		// Linear fields are preserved by the vertex interpolation, up to the sphere curvature.
+		let storage = Storage::from_fn(Element::Vertex, 2, |p| p.x);
+		let fine = storage.upsample(4);
+		assert_eq!(fine.len(), Vertex::count(4) as usize);
+		for v in Vertex::all(2) {
+			assert_eq!(fine.vertex(v.refine(4)), storage.vertex(v))
+		}
+
+		let dir = Vec3::new(0.3, -0.5, 0.8).normalize();
+		assert!((fine.sample(dir).unwrap() - dir.x).abs() < 0.05);
+
+		let coarse = fine.downsample(2, Reduction::Max);
+		assert!(Vertex::all(2).all(|v| coarse.vertex(v) >= storage.vertex(v)));
+
+		let faces = Storage::from_fn(Element::Face, 3, |p| p.z);
+		let mean = faces.upsample(5).downsample(3, Reduction::Mean);
+		for cell in Cell::all(3) {
+			assert!((mean.face(cell).unwrap() - faces.face(cell).unwrap()).abs() < 1e-6)
+		}
+
+		// Sparse storage only covers its regions.
+		let region = Cell::new(1, 9);
+		let mut sparse = Storage::sparse(Element::Vertex, 3);
+		sparse.fill_region(region, |p| p.y);
+		let fine = sparse.upsample(5);
+		assert!(fine.is_sparse());
+		assert_eq!(fine.regions(), &[region]);
+		assert!((fine.sample(region.center()).unwrap() - region.center().y).abs() < 0.05);
+		assert!(fine.sample(-region.center()).is_none());
+		assert_eq!(fine.downsample(0, Reduction::Min).regions(), &[Cell::new(0, 2)])
+	}
+}
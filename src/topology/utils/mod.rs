@@ -45,6 +45,30 @@ pub fn rbpi_next(n: u32, depth: u32) -> u32 {
 	n | (1 << depth)
 }
 
+/// RBPI index of the `position`-th point of a scale of `2^depth` intervals.
+///
+/// The index of a point does not depend on the depth of the scale.
+#[inline]
+pub fn rbpi(position: u32, depth: u32) -> u32 {
+	if depth == 0 {
+		0
+	} else {
+		position.reverse_bits() >> (32 - depth)
+	}
+}
+
+/// Normalized RBPI index of the `position`-th point of a scale of `2^depth` intervals.
+///
+/// The last point of the scale has index 0.
+#[inline]
+pub fn nrbpi(position: u32, depth: u32) -> u32 {
+	if position == 1 << depth {
+		0
+	} else {
+		rbpi_to_nrbpi(rbpi(position, depth))
+	}
+}
+
 /// From RBPI space to normalized RBPI space.
 #[inline]
 pub fn rbpi_to_nrbpi(a: u32) -> u32 {
@@ -136,4 +160,12 @@ mod test {
 		assert_eq!(nrbpi_mean(2, 4), 6);
 		assert_eq!(nrbpi_mean(4, 0), 8);
 	}
+
+	#[test]
+	fn nrbpi() {
+		use super::nrbpi;
+		let scale: Vec<u32> = (0..=16).map(|p| nrbpi(p, 4)).collect();
+		assert_eq!(scale, vec![1, 9, 5, 13, 3, 11, 7, 15, 2, 10, 6, 14, 4, 12, 8, 16, 0]);
+		assert_eq!(nrbpi(1, 1), nrbpi(8, 4));
+	}
 }
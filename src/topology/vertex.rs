@@ -0,0 +1,262 @@
+use glam::Vec3;
+use super::Cell;
+use super::sphere::ICOSAHEDRON_FACES;
+use super::utils::{nrbpi, elegant_pair};
+
+/// Edges of the icosahedron, as sorted pairs of vertex indexes, in lexicographic order.
+const ICOSAHEDRON_EDGES: [(usize, usize); 30] = icosahedron_edges();
+
+const fn icosahedron_edges() -> [(usize, usize); 30] {
+	let mut edges = [(0, 0); 30];
+	let mut count = 0;
+	let mut a = 0;
+	while a < 12 {
+		let mut b = a + 1;
+		while b < 12 {
+			if adjacent(a, b) {
+				edges[count] = (a, b);
+				count += 1
+			}
+
+			b += 1
+		}
+
+		a += 1
+	}
+
+	edges
+}
+
+/// Checks if the two icosahedron vertices share a face.
+const fn adjacent(a: usize, b: usize) -> bool {
+	let mut f = 0;
+	while f < 20 {
+		let face = ICOSAHEDRON_FACES[f];
+		let has_a = face[0] == a || face[1] == a || face[2] == a;
+		let has_b = face[0] == b || face[1] == b || face[2] == b;
+		if has_a && has_b {
+			return true
+		}
+
+		f += 1
+	}
+
+	false
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Kind {
+	/// Vertex of the icosahedron.
+	Corner(u32),
+
+	/// Point inside an icosahedron edge, at the given position from its lowest vertex.
+	Edge(u32, u32),
+
+	/// Point inside an icosahedron face, with its lattice coordinates in the face.
+	Face(u32, u32, u32)
+}
+
+/// Vertex of the icosahedral subdivision at a given depth.
+///
+/// Vertices are indexed from 0 to [`Vertex::count`], starting with the 12 icosahedron vertices,
+/// then the points inside the 30 icosahedron edges, then the points inside the 20 faces.
+/// Their [`id`](Vertex::id) is built from the normalized RBPI indexes of their coordinates,
+/// so that a point keeps the same id at every depth.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Vertex {
+	depth: u32,
+	kind: Kind
+}
+
+impl Vertex {
+	pub fn new(depth: u32, index: u32) -> Vertex {
+		debug_assert!(index < Vertex::count(depth));
+		let n = 1 << depth;
+		let m = n - 1;
+		let kind = if index < 12 {
+			Kind::Corner(index)
+		} else if index < 12 + 30 * m {
+			Kind::Edge((index - 12) / m, (index - 12) % m + 1)
+		} else {
+			let rest = index - 12 - 30 * m;
+			let t = m * (m - 1) / 2;
+			let (face, mut k) = (rest / t, rest % t);
+			let mut i = 1;
+			while k >= m - i {
+				k -= m - i;
+				i += 1
+			}
+
+			Kind::Face(face, i, k + 1)
+		};
+
+		Vertex {
+			depth,
+			kind
+		}
+	}
+
+	/// Number of vertices at the given depth.
+	pub const fn count(depth: u32) -> u32 {
+		10 * (1 << (2 * depth)) + 2
+	}
+
+	/// Iterator over all the vertices of the given depth.
+	pub fn all(depth: u32) -> impl Iterator<Item = Vertex> {
+		(0..Vertex::count(depth)).map(move |index| Vertex::new(depth, index))
+	}
+
+	/// Vertex at the given lattice coordinates of an icosahedron face (see [`Cell::lattice`]).
+	pub(crate) fn from_lattice(depth: u32, face: u32, (i, j): (u32, u32)) -> Vertex {
+		let n = 1 << depth;
+		let corners = ICOSAHEDRON_FACES[face as usize];
+		let kind = if i == 0 && j == 0 {
+			Kind::Corner(corners[0] as u32)
+		} else if i == n {
+			Kind::Corner(corners[1] as u32)
+		} else if j == n {
+			Kind::Corner(corners[2] as u32)
+		} else if i == 0 || j == 0 || i + j == n {
+			let (p, q, x) = if i + j == n {
+				(corners[1], corners[2], j)
+			} else if j == 0 {
+				(corners[0], corners[1], i)
+			} else {
+				(corners[0], corners[2], j)
+			};
+
+			let (edge, x) = if p < q { ((p, q), x) } else { ((q, p), n - x) };
+			let index = ICOSAHEDRON_EDGES.iter().position(|e| *e == edge).unwrap();
+			Kind::Edge(index as u32, x)
+		} else {
+			Kind::Face(face, i, j)
+		};
+
+		Vertex {
+			depth,
+			kind
+		}
+	}
+
+	/// Icosahedron face containing the vertex, and its lattice coordinates in this face.
+	pub(crate) fn lattice(&self) -> (u32, (u32, u32)) {
+		let n = 1 << self.depth;
+		let coordinates = |k: usize| [(0, 0), (n, 0), (0, n)][k];
+		match self.kind {
+			Kind::Corner(v) => {
+				let (face, corners) = ICOSAHEDRON_FACES.iter().enumerate().find(|(_, f)| f.contains(&(v as usize))).unwrap();
+				(face as u32, coordinates(corners.iter().position(|c| *c == v as usize).unwrap()))
+			},
+			Kind::Edge(e, x) => {
+				let (p, q) = ICOSAHEDRON_EDGES[e as usize];
+				let (face, corners) = ICOSAHEDRON_FACES.iter().enumerate().find(|(_, f)| f.contains(&p) && f.contains(&q)).unwrap();
+				let a = coordinates(corners.iter().position(|c| *c == p).unwrap());
+				let b = coordinates(corners.iter().position(|c| *c == q).unwrap());
+				(face as u32, ((a.0 * (n - x) + b.0 * x) / n, (a.1 * (n - x) + b.1 * x) / n))
+			},
+			Kind::Face(face, i, j) => (face, (i, j))
+		}
+	}
+
+	pub fn depth(&self) -> u32 {
+		self.depth
+	}
+
+	/// Index of the vertex among the vertices of its depth.
+	pub fn index(&self) -> u32 {
+		let n = 1 << self.depth;
+		let m = n - 1;
+		match self.kind {
+			Kind::Corner(v) => v,
+			Kind::Edge(e, x) => 12 + e * m + x - 1,
+			Kind::Face(face, i, j) => {
+				let row = (i - 1) * m - (i - 1) * i / 2;
+				12 + 30 * m + face * (m * (m - 1) / 2) + row + j - 1
+			}
+		}
+	}
+
+	/// Unique identifier of the point, the same at every depth.
+	pub fn id(&self) -> u64 {
+		match self.kind {
+			Kind::Corner(v) => v as u64,
+			Kind::Edge(e, x) => (1 << 40) | ((e as u64) << 32) | nrbpi(x, self.depth) as u64,
+			Kind::Face(face, i, j) => (2 << 40) | ((face as u64) << 32) | elegant_pair(nrbpi(i, self.depth), nrbpi(j, self.depth)) as u64
+		}
+	}
+
+	/// Same point, at the given depth (which must not be lower than the vertex depth).
+	pub fn refine(&self, depth: u32) -> Vertex {
+		let shift = depth - self.depth;
+		let kind = match self.kind {
+			Kind::Corner(v) => Kind::Corner(v),
+			Kind::Edge(e, x) => Kind::Edge(e, x << shift),
+			Kind::Face(face, i, j) => Kind::Face(face, i << shift, j << shift)
+		};
+
+		Vertex {
+			depth,
+			kind
+		}
+	}
+
+	/// A cell of the same depth having this vertex as a corner, and the index of this corner.
+	pub fn cell(&self) -> (Cell, usize) {
+		let (face, (i, j)) = self.lattice();
+		let p = (i as i64, j as i64);
+		let depth = self.depth;
+		let contains = |c: Cell| {
+			// Lattice coordinates at the vertex depth.
+			let corners = c.lattice().1;
+			let scale = 1i64 << (depth - c.depth());
+			let mut sides = [0i64; 3];
+			for k in 0..3 {
+				let (a, b) = (corners[k], corners[(k + 1) % 3]);
+				let (ax, ay) = (a.0 as i64 * scale, a.1 as i64 * scale);
+				let (bx, by) = (b.0 as i64 * scale, b.1 as i64 * scale);
+				sides[k] = (bx - ax) * (p.1 - ay) - (by - ay) * (p.0 - ax)
+			}
+
+			sides.iter().all(|s| *s >= 0) || sides.iter().all(|s| *s <= 0)
+		};
+
+		let mut cell = Cell::new(0, face);
+		for _ in 0..self.depth {
+			cell = *cell.children().iter().find(|c| contains(**c)).unwrap()
+		}
+
+		let corner = cell.lattice().1.iter().position(|c| *c == (i, j)).unwrap();
+		(cell, corner)
+	}
+
+	/// Position of the vertex, on the unit sphere.
+	///
+	/// It is identical to the matching corner of the cells around the vertex.
+	pub fn position(&self) -> Vec3 {
+		let (cell, corner) = self.cell();
+		cell.corners()[corner]
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::topology::Cell;
+	use super::Vertex;
+
+	#[test]
+	fn indexes() {
+		for depth in 0..4 {
+			for (index, v) in Vertex::all(depth).enumerate() {
+				assert_eq!(v.index() as usize, index);
+				assert_eq!(v.refine(depth + 2).id(), v.id());
+				assert!(v.position().dot(v.refine(depth + 1).position()) > 1.0 - 1e-6)
+			}
+
+			for cell in Cell::all(depth) {
+				for (v, c) in cell.vertices().iter().zip(cell.corners().iter()) {
+					assert_eq!(v.position(), *c)
+				}
+			}
+		}
+	}
+}